@@ -0,0 +1,117 @@
+use crate::renderer::SCALE;
+
+use routing::tile::Tile;
+use routing::tile_map::{TileMap, TILE_SIZE_F};
+use routing::vehicle::Vehicle;
+
+use nalgebra::{Point2, Vector2};
+
+pub const MIN_ZOOM:   f32 = 1.0;
+pub const MAX_ZOOM:   f32 = 64.0;
+pub const FIT_MARGIN: f32 = 0.9;
+
+pub struct Camera {
+    pub center:   Point2<f32>,
+    pub zoom:     f32,
+    pub viewport: Vector2<f32>,
+    pub extent:   Vector2<f32>,
+    pub follow:   Option<u32>,
+}
+
+impl Camera {
+    pub fn new(viewport: Vector2<f32>) -> Self {
+        Self {
+            center: Point2::origin(),
+            zoom:   SCALE,
+            viewport,
+            extent: Vector2::zeros(),
+            follow: None,
+        }
+    }
+
+    pub fn to_screen(&self, world: Point2<f32>) -> Point2<f32> {
+        Point2::from((world - self.center) * self.zoom + self.viewport / 2.0)
+    }
+
+    pub fn to_world(&self, screen: Point2<f32>) -> Point2<f32> {
+        self.center + (screen.coords - self.viewport / 2.0) / self.zoom
+    }
+
+    pub fn pan(&mut self, delta: Vector2<f32>) {
+        self.center -= delta / self.zoom;
+        self.follow  = None;
+    }
+
+    pub fn zoom_at(&mut self, screen: Point2<f32>, factor: f32) {
+        let before = self.to_world(screen);
+
+        self.zoom = (self.zoom * factor).clamp(self.min_zoom(), MAX_ZOOM);
+
+        if self.follow.is_none() {
+            self.center += before - self.to_world(screen);
+        }
+    }
+
+    pub fn min_zoom(&self) -> f32 {
+        let fitted = (self.viewport.x / self.extent.x).min(self.viewport.y / self.extent.y) * FIT_MARGIN;
+
+        fitted.min(MIN_ZOOM).max(f32::EPSILON)
+    }
+
+    pub fn fit(&mut self, tiles: &TileMap<Tile>) {
+        let Some(first) = tiles.tiles.first() else {
+            return;
+        };
+
+        let (min, max) =
+            tiles.tiles
+                .iter()
+                .fold((first.pos, first.pos), |(min, max), tile| {
+                    (
+                        Point2::new(min.x.min(tile.pos.x), min.y.min(tile.pos.y)),
+                        Point2::new(max.x.max(tile.pos.x), max.y.max(tile.pos.y)),
+                    )
+                });
+
+        self.extent = Vector2::zeros();
+        self.fit_region(min, max);
+    }
+
+    pub fn fit_region(&mut self, min: Point2<i32>, max: Point2<i32>) {
+        let min = Point2::new(min.x as f32, min.y as f32) * TILE_SIZE_F;
+        let max = Point2::new(max.x as f32, max.y as f32) * TILE_SIZE_F;
+
+        let size = max - min + Vector2::repeat(TILE_SIZE_F);
+
+        self.center = min + (max - min) / 2.0;
+        self.extent = self.extent.sup(&size);
+        self.follow = None;
+        self.zoom   =
+            ((self.viewport.x / size.x).min(self.viewport.y / size.y) * FIT_MARGIN)
+                .clamp(self.min_zoom(), MAX_ZOOM);
+    }
+
+    pub fn track(&mut self, vehicles: &[Vehicle]) {
+        let Some(id) = self.follow else {
+            return;
+        };
+
+        match vehicles.iter().find(|vehicle| vehicle.id == id) {
+            Some(vehicle) => self.center = vehicle.center(),
+            None          => self.follow = None,
+        }
+    }
+
+    pub fn follow_next(&mut self, vehicles: &[Vehicle]) {
+        let next =
+            vehicles
+                .iter()
+                .map(|vehicle| vehicle.id)
+                .filter(|&id| self.follow.is_none_or(|current| id > current))
+                .min()
+                .or_else(|| vehicles.iter().map(|vehicle| vehicle.id).min());
+
+        self.follow = next;
+        self.track(vehicles);
+    }
+}
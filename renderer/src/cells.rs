@@ -0,0 +1,193 @@
+use crate::frontend::World;
+
+use routing::direction::{Cardinal, Direction, TileDirection};
+use routing::priority_sign::Priority;
+use routing::segment_bounds::SegmentBounds;
+use routing::stoplight::Signal;
+use routing::tile::Tile;
+use routing::tile_map::TILE_SIZE_F;
+use routing::traffic_control::ControlState;
+
+use nalgebra::Point2;
+
+pub const COLUMNS_PER_TILE: f32 = 2.0;
+
+const HEADINGS: [char; 8] = ['↑', '↗', '→', '↘', '↓', '↙', '←', '↖'];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tint {
+    Plain,
+    Road,
+    Junction,
+    Red,
+    Yellow,
+    Green,
+    Vehicle,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Cell {
+    pub glyph: char,
+    pub tint:  Tint,
+}
+
+impl Cell {
+    pub const EMPTY: Self = Self { glyph: ' ', tint: Tint::Plain };
+}
+
+pub struct CellGrid {
+    pub width:  usize,
+    pub height: usize,
+    pub cells:  Vec<Cell>,
+}
+
+impl CellGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::EMPTY; width * height],
+        }
+    }
+
+    pub fn compose(world: &World, center: Point2<f32>, width: usize, height: usize) -> Self {
+        let mut grid = Self::new(width, height);
+
+        grid.project(world, center);
+
+        grid
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> Option<Cell> {
+        (col < self.width && row < self.height).then(|| self.cells[row * self.width + col])
+    }
+
+    pub fn set(&mut self, col: i32, row: i32, cell: Cell) {
+        if col < 0 || row < 0 || col as usize >= self.width || row as usize >= self.height {
+            return;
+        }
+
+        self.cells[row as usize * self.width + col as usize] = cell;
+    }
+
+    pub fn text(&mut self, col: i32, row: i32, text: &str, tint: Tint) {
+        for (idx, glyph) in text.chars().enumerate() {
+            self.set(col + idx as i32, row, Cell { glyph, tint });
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    fn project(&mut self, world: &World, center: Point2<f32>) {
+        let origin = Point2::new(self.width as f32 / 2.0, self.height as f32 / 2.0);
+
+        let cell = |pos: Point2<f32>| {
+            (
+                (origin.x + (pos.x - center.x) / TILE_SIZE_F * COLUMNS_PER_TILE).round() as i32,
+                (origin.y + (pos.y - center.y) / TILE_SIZE_F).round() as i32,
+            )
+        };
+
+        for tile in &world.tiles.tiles {
+            let (col, row) = cell(Point2::new(tile.pos.x as f32, tile.pos.y as f32) * TILE_SIZE_F);
+
+            let tint =
+                match tile.dir {
+                    TileDirection::Constant(_)     => Tint::Road,
+                    TileDirection::Intersection(_) => Tint::Junction,
+                };
+
+            self.set(col, row, Cell { glyph: glyph(tile), tint });
+
+            let right = world.tiles.get(&(tile.pos + Cardinal::Right.offset())).is_some();
+
+            if right && connects(tile, Cardinal::Right) {
+                self.set(col + 1, row, Cell { glyph: '─', tint });
+            }
+        }
+
+        let midpoint = |line: &SegmentBounds| Point2::from((line.0.coords + line.1.coords) / 2.0);
+
+        for control in world.controls {
+            match control.state() {
+                ControlState::StopSign { lines, .. } => {
+                    for line in &lines {
+                        let (col, row) = cell(midpoint(line));
+                        self.set(col, row, Cell { glyph: '■', tint: Tint::Red });
+                    }
+                }
+
+                ControlState::PrioritySign { priority, lines } => {
+                    let tint =
+                        match priority {
+                            Priority::Stop  => Tint::Red,
+                            Priority::Yield => Tint::Yellow,
+                        };
+
+                    for line in &lines {
+                        let (col, row) = cell(midpoint(line));
+                        self.set(col, row, Cell { glyph: '▼', tint });
+                    }
+                }
+
+                ControlState::Stoplight { heads, .. } => {
+                    for head in heads {
+                        let tint =
+                            match head.signal {
+                                Signal::Red    => Tint::Red,
+                                Signal::Yellow => Tint::Yellow,
+                                Signal::Green  => Tint::Green,
+                            };
+
+                        let (col, row) = cell(midpoint(&head.line));
+                        self.set(col, row, Cell { glyph: '●', tint });
+                    }
+                }
+            }
+        }
+
+        for vehicle in world.vehicles {
+            let (col, row) = cell(vehicle.center());
+
+            let heading = (vehicle.dir.degrees() / 45.0).round() as usize % HEADINGS.len();
+
+            self.set(col, row, Cell { glyph: HEADINGS[heading], tint: Tint::Vehicle });
+        }
+    }
+}
+
+fn glyph(tile: &Tile) -> char {
+    match tile.dir {
+        TileDirection::Intersection(_) => '┼',
+
+        TileDirection::Constant(Direction::Straight(dir)) => {
+            match dir {
+                Cardinal::Left | Cardinal::Right => '─',
+                Cardinal::Up   | Cardinal::Down  => '│',
+            }
+        }
+
+        TileDirection::Constant(Direction::Turn(in_dir, out_dir)) => {
+            let sides = [in_dir.opposite(), out_dir];
+
+            let touches = |side: Cardinal| sides.contains(&side);
+
+            match (touches(Cardinal::Left), touches(Cardinal::Up)) {
+                (true,  true)  => '┘',
+                (true,  false) => '┐',
+                (false, true)  => '└',
+                (false, false) => '┌',
+            }
+        }
+    }
+}
+
+fn connects(tile: &Tile, side: Cardinal) -> bool {
+    match &tile.dir {
+        TileDirection::Intersection(_) => true,
+
+        TileDirection::Constant(dir) => dir.in_dir().opposite() == side || dir.out_dir() == side,
+    }
+}
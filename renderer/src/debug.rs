@@ -0,0 +1,108 @@
+use crate::camera::Camera;
+use crate::frontend::World;
+
+use routing::bounds::Bounds;
+use routing::segment_bounds::SegmentBounds;
+use routing::stoplight::Signal;
+use routing::traffic_control::ControlState;
+use routing::vehicle::Brake;
+
+use skia_safe::{Canvas, Color, Paint, PaintStyle, Point};
+
+use nalgebra::Point2;
+
+pub const LINE_WIDTH: f32 = 2.0;
+
+pub fn draw(canvas: &Canvas, camera: &Camera, world: &World) {
+    let width = LINE_WIDTH / camera.zoom;
+
+    let stroke = |color: Color, scale: f32| {
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(width * scale);
+        paint.set_color(color);
+        paint
+    };
+
+    let active   = stroke(Color::RED, 1.0);
+    let inactive = stroke(Color::GREEN, 1.0);
+    let trigger  = stroke(Color::MAGENTA, 2.0);
+
+    for (idx, control) in world.controls.iter().enumerate() {
+        let braking =
+            world.vehicles
+                .iter()
+                .filter(|vehicle| vehicle.brake == Some(Brake::Control(idx)))
+                .collect::<Vec<_>>();
+
+        for (line, blocking) in lines(control.state()) {
+            let triggered =
+                braking
+                    .iter()
+                    .any(|vehicle| vehicle.collider().colliding(&line));
+
+            let paint =
+                match (triggered, blocking) {
+                    (true, _)      => &trigger,
+                    (false, true)  => &active,
+                    (false, false) => &inactive,
+                };
+
+            canvas.draw_line(point(line.0), point(line.1), paint);
+        }
+    }
+
+    let body     = stroke(Color::BLUE, 1.0);
+    let collider = stroke(Color::from_argb(160, 0, 160, 200), 1.0);
+    let braking  = stroke(Color::from_rgb(255, 140, 0), 1.0);
+    let culprit  = stroke(Color::MAGENTA, 2.0);
+
+    for vehicle in world.vehicles {
+        let look_ahead =
+            if vehicle.brake.is_some() {
+                &braking
+            } else {
+                &collider
+            };
+
+        outline(canvas, vehicle.collider().corners(), look_ahead);
+        outline(canvas, vehicle.bounds().corners(), &body);
+
+        let Some(Brake::Vehicle(id)) = vehicle.brake else {
+            continue;
+        };
+
+        if let Some(other) = world.vehicles.iter().find(|other| other.id == id) {
+            outline(canvas, other.bounds().corners(), &culprit);
+            canvas.draw_line(point(vehicle.center()), point(other.center()), &braking);
+        }
+    }
+}
+
+fn lines(state: ControlState) -> Vec<(SegmentBounds, bool)> {
+    match state {
+        ControlState::StopSign { lines, .. } | ControlState::PrioritySign { lines, .. } => {
+            lines.into_iter().map(|line| (line, true)).collect()
+        }
+
+        ControlState::Stoplight { heads, .. } => {
+            heads
+                .into_iter()
+                .map(|head| (head.line, head.signal != Signal::Green))
+                .collect()
+        }
+    }
+}
+
+fn outline(canvas: &Canvas, corners: [Point2<f32>; 4], paint: &Paint) {
+    for idx in 0..corners.len() {
+        let next = (idx + 1) % corners.len();
+
+        canvas.draw_line(point(corners[idx]), point(corners[next]), paint);
+    }
+}
+
+fn point(pos: Point2<f32>) -> Point {
+    Point::new(pos.x, pos.y)
+}
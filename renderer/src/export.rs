@@ -0,0 +1,199 @@
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+
+use anyhow::{bail, Context, Result};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+pub const FPS:       u32 = 30;
+pub const GIF_SPEED: i32 = 10;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    Gif,
+    Apng,
+    Ffmpeg,
+}
+
+impl Format {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        let extension =
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .map(str::to_ascii_lowercase)
+                .with_context(|| format!("cannot tell the export format of {}", path.display()))?;
+
+        match extension.as_str() {
+            "gif"                          => Ok(Self::Gif),
+            "png" | "apng"                 => Ok(Self::Apng),
+            "mp4" | "mkv" | "webm" | "mov" => Ok(Self::Ffmpeg),
+
+            _ => bail!("unsupported export format {extension:?}"),
+        }
+    }
+}
+
+enum Sink {
+    Gif(GifEncoder<BufWriter<File>>),
+    Apng(Vec<RgbaImage>),
+    Ffmpeg(Child),
+}
+
+pub struct Exporter {
+    pub path:   PathBuf,
+    pub format: Format,
+    pub fps:    u32,
+    pub ffmpeg: PathBuf,
+
+    size:   Option<(u32, u32)>,
+    sink:   Option<Sink>,
+    frames: u32,
+}
+
+impl Exporter {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        Ok(Self {
+            format: Format::from_path(&path)?,
+            path,
+            fps:    FPS,
+            ffmpeg: PathBuf::from("ffmpeg"),
+            size:   None,
+            sink:   None,
+            frames: 0,
+        })
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn push(&mut self, frame: &RgbaImage) -> Result<()> {
+        let size     = frame.dimensions();
+        let expected = *self.size.get_or_insert(size);
+
+        if size != expected {
+            bail!("frame {} is {}x{}, expected {}x{}", self.frames, size.0, size.1, expected.0, expected.1);
+        }
+
+        if self.sink.is_none() {
+            self.sink = Some(self.open(size)?);
+        }
+
+        match self.sink.as_mut() {
+            Some(Sink::Gif(encoder)) => {
+                let delay = Delay::from_numer_denom_ms(1000, self.fps.max(1));
+
+                encoder
+                    .encode_frame(Frame::from_parts(frame.clone(), 0, 0, delay))
+                    .context("failed to encode GIF frame")?;
+            }
+
+            Some(Sink::Apng(frames)) => frames.push(frame.clone()),
+
+            Some(Sink::Ffmpeg(child)) => {
+                child.stdin
+                    .as_mut()
+                    .context("ffmpeg stdin is closed")?
+                    .write_all(frame.as_raw())
+                    .context("failed to pipe frame to ffmpeg")?;
+            }
+
+            None => unreachable!(),
+        }
+
+        self.frames += 1;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        match self.sink.take() {
+            None => bail!("no frames to export to {}", self.path.display()),
+
+            Some(Sink::Gif(encoder)) => {
+                drop(encoder);
+                Ok(())
+            }
+
+            Some(Sink::Apng(frames)) => self.write_apng(&frames),
+
+            Some(Sink::Ffmpeg(mut child)) => {
+                drop(child.stdin.take());
+
+                let status = child.wait().context("failed to wait for ffmpeg")?;
+
+                if !status.success() {
+                    bail!("ffmpeg exited with {status}");
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn open(&self, (width, height): (u32, u32)) -> Result<Sink> {
+        match self.format {
+            Format::Gif => {
+                let file = self.create()?;
+
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), GIF_SPEED);
+                encoder.set_repeat(Repeat::Infinite).context("failed to write GIF header")?;
+
+                Ok(Sink::Gif(encoder))
+            }
+
+            Format::Apng => Ok(Sink::Apng(Vec::new())),
+
+            Format::Ffmpeg => {
+                let child =
+                    Command::new(&self.ffmpeg)
+                        .args(["-y", "-loglevel", "error"])
+                        .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+                        .args(["-s", &format!("{width}x{height}")])
+                        .args(["-r", &self.fps.to_string()])
+                        .args(["-i", "-"])
+                        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+                        .args(["-pix_fmt", "yuv420p"])
+                        .arg(&self.path)
+                        .stdin(Stdio::piped())
+                        .spawn()
+                        .with_context(|| format!("failed to run {}", self.ffmpeg.display()))?;
+
+                Ok(Sink::Ffmpeg(child))
+            }
+        }
+    }
+
+    fn create(&self) -> Result<File> {
+        File::create(&self.path).with_context(|| format!("failed to create {}", self.path.display()))
+    }
+
+    fn write_apng(&self, frames: &[RgbaImage]) -> Result<()> {
+        let Some((width, height)) = self.size else {
+            bail!("no frames to export to {}", self.path.display());
+        };
+
+        let mut encoder = png::Encoder::new(BufWriter::new(self.create()?), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, 0)?;
+        encoder.set_frame_delay(1, self.fps.clamp(1, u16::MAX as u32) as u16)?;
+
+        let mut writer = encoder.write_header().context("failed to write APNG header")?;
+
+        for frame in frames {
+            writer.write_image_data(frame.as_raw()).context("failed to encode APNG frame")?;
+        }
+
+        writer.finish().context("failed to finish APNG")?;
+
+        Ok(())
+    }
+}
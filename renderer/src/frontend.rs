@@ -0,0 +1,25 @@
+use crate::camera::Camera;
+use crate::scene::Overlay;
+
+use routing::vehicle::Vehicle;
+use routing::tile::Tile;
+use routing::tile_map::TileMap;
+use routing::traffic_control::TrafficControl;
+
+use anyhow::Result;
+
+#[derive(Clone, Copy)]
+pub struct World<'a> {
+    pub time:     u32,
+    pub vehicles: &'a [Vehicle],
+    pub tiles:    &'a TileMap<Tile>,
+    pub controls: &'a [Box<dyn TrafficControl>],
+    pub overlay:  Option<&'a Overlay<'a>>,
+    pub debug:    bool,
+}
+
+pub trait Frontend {
+    fn camera(&mut self) -> &mut Camera;
+
+    fn draw(&mut self, world: &World) -> Result<()>;
+}
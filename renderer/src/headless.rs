@@ -0,0 +1,129 @@
+use crate::camera::Camera;
+use crate::frontend::{Frontend, World};
+use crate::scene;
+use crate::texture_loader::TextureLoader;
+
+use skia_safe::{surfaces, AlphaType, ColorType, ImageInfo, Surface};
+use image::RgbaImage;
+
+use nalgebra::Vector2;
+use anyhow::{bail, Context, Result};
+
+use std::path::{Path, PathBuf};
+
+pub struct Headless {
+    surface: Surface,
+
+    pub loader: TextureLoader,
+    pub camera: Camera,
+}
+
+impl Headless {
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        if width == 0 || height == 0 {
+            bail!("frame size must be positive, got {width}x{height}");
+        }
+
+        let surface =
+            surfaces::raster_n32_premul((width as i32, height as i32))
+                .context("failed to create raster surface")?;
+
+        Ok(Self {
+            surface,
+            loader: TextureLoader::new()?,
+            camera: Camera::new(Vector2::new(width as f32, height as f32)),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.surface.width() as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.surface.height() as u32
+    }
+
+    pub fn snapshot(&mut self) -> Result<RgbaImage> {
+        let (width, height) = (self.width(), self.height());
+
+        let info = ImageInfo::new(
+            (width as i32, height as i32),
+            ColorType::RGBA8888,
+            AlphaType::Unpremul,
+            None,
+        );
+
+        let mut pixels = vec![0; (width * height * 4) as usize];
+
+        if !self.surface.read_pixels(&info, &mut pixels, (width * 4) as usize, (0, 0)) {
+            bail!("failed to read back rendered frame");
+        }
+
+        RgbaImage::from_raw(width, height, pixels).context("frame buffer has the wrong size")
+    }
+
+    pub fn render(&mut self, world: &World) -> Result<RgbaImage> {
+        self.draw(world)?;
+        self.snapshot()
+    }
+
+    pub fn save<P: AsRef<Path>>(&mut self, path: P, world: &World) -> Result<()> {
+        let path = path.as_ref();
+
+        self.render(world)?
+            .save(path)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+impl Frontend for Headless {
+    fn camera(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    fn draw(&mut self, world: &World) -> Result<()> {
+        self.camera.track(world.vehicles);
+
+        scene::draw(self.surface.canvas(), &self.loader, &self.camera, world);
+
+        Ok(())
+    }
+}
+
+pub struct FrameDump {
+    pub dir:    PathBuf,
+    pub prefix: String,
+
+    count: u32,
+}
+
+impl FrameDump {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+
+        Ok(Self {
+            dir,
+            prefix: String::from("frame"),
+            count:  0,
+        })
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn write(&mut self, frame: &RgbaImage) -> Result<()> {
+        let path = self.dir.join(format!("{}-{:06}.png", self.prefix, self.count));
+
+        frame
+            .save(&path)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        self.count += 1;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use skia_safe::{Canvas, Color, Font, FontMgr, FontStyle, Paint, Rect};
+
+pub const FONT_SIZE:   f32 = 28.0;
+pub const LINE_HEIGHT: f32 = 1.3;
+pub const PADDING:     f32 = 16.0;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+}
+
+pub struct Hud {
+    pub lines:   Vec<String>,
+    pub visible: bool,
+    pub corner:  Corner,
+
+    font: Font,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        let font =
+            FontMgr::default()
+                .legacy_make_typeface(None, FontStyle::normal())
+                .map(|typeface| Font::from_typeface(typeface, FONT_SIZE))
+                .unwrap_or_default();
+
+        Self {
+            lines:   Vec::new(),
+            visible: true,
+            corner:  Corner::TopLeft,
+            font,
+        }
+    }
+
+    pub fn draw(&self, canvas: &Canvas, viewport: f32) {
+        if !self.visible || self.lines.is_empty() {
+            return;
+        }
+
+        let step = FONT_SIZE * LINE_HEIGHT;
+
+        let width =
+            self.lines
+                .iter()
+                .map(|line| self.font.measure_str(line, None).0)
+                .fold(0.0, f32::max);
+
+        let left =
+            match self.corner {
+                Corner::TopLeft  => PADDING,
+                Corner::TopRight => viewport - width - PADDING * 3.0,
+            };
+
+        let panel = Rect::from_xywh(
+            left,
+            PADDING,
+            width + PADDING * 2.0,
+            step * self.lines.len() as f32 + PADDING * 2.0,
+        );
+
+        let mut background = Paint::default();
+        background.set_anti_alias(true);
+        background.set_color(Color::from_argb(176, 0, 0, 0));
+
+        canvas.draw_round_rect(panel, PADDING / 2.0, PADDING / 2.0, &background);
+
+        let mut text = Paint::default();
+        text.set_anti_alias(true);
+        text.set_color(Color::WHITE);
+
+        for (idx, line) in self.lines.iter().enumerate() {
+            let baseline = panel.top + PADDING + step * idx as f32 + FONT_SIZE;
+
+            canvas.draw_str(line, (panel.left + PADDING, baseline), &self.font, &text);
+        }
+    }
+}
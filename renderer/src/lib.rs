@@ -1,2 +1,12 @@
+pub mod camera;
+pub mod cells;
+pub mod debug;
+pub mod export;
+pub mod frontend;
+pub mod headless;
+pub mod hud;
 pub mod renderer;
+pub mod scene;
+pub mod svg;
+pub mod terminal;
 pub mod texture_loader;
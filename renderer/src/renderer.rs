@@ -1,11 +1,9 @@
+use crate::camera::Camera;
+use crate::frontend::{Frontend, World};
+use crate::hud::{Corner, Hud};
+use crate::scene;
 use crate::texture_loader::TextureLoader;
 
-use routing::vehicle::Vehicle;
-use routing::stop_sign::StopSign;
-use routing::stoplight::Stoplight;
-use routing::tile::Tile;
-use routing::tile_map::{TILE_SIZE_F, TileMap};
-
 use gl::types::GLint;
 use glutin::config::{ConfigTemplateBuilder, GlConfig};
 use glutin::context::{ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext};
@@ -14,7 +12,7 @@ use glutin::surface::{GlSurface, SurfaceAttributesBuilder, WindowSurface};
 
 use skia_safe::gpu::gl::{FramebufferInfo, Interface};
 use skia_safe::gpu::{backend_render_targets, surfaces, DirectContext, SurfaceOrigin};
-use skia_safe::{Color, ColorType, Paint, Point, Surface};
+use skia_safe::{ColorType, Surface};
 
 use winit::dpi::PhysicalSize;
 use winit::event_loop::EventLoop;
@@ -22,6 +20,7 @@ use winit::window::{Window, WindowBuilder};
 use glutin_winit::DisplayBuilder;
 use raw_window_handle::HasRawWindowHandle;
 
+use nalgebra::{Point2, Vector2};
 use anyhow::{Result, Context};
 
 use std::ffi::CString;
@@ -38,7 +37,10 @@ pub struct Renderer {
     gl_context: PossiblyCurrentContext,
     gl_surface: glutin::surface::Surface<WindowSurface>,
 
-    loader: TextureLoader,
+    pub loader:    TextureLoader,
+    pub camera:    Camera,
+    pub hud:       Hud,
+    pub inspector: Hud,
 }
 
 impl Renderer {
@@ -132,6 +134,10 @@ impl Renderer {
         )?;
 
         let loader = TextureLoader::new()?;
+        let camera = Camera::new(Vector2::new(width as f32, height as f32));
+
+        let mut inspector = Hud::new();
+        inspector.corner  = Corner::TopRight;
 
         Ok((
             Self {
@@ -141,6 +147,9 @@ impl Renderer {
                 gl_context,
                 gl_surface,
                 loader,
+                camera,
+                hud: Hud::new(),
+                inspector,
                 gl_config: Box::new(gl_config),
             },
             event_loop,
@@ -197,97 +206,34 @@ impl Renderer {
             NonZeroU32::new(self.window.inner_size().height).context("")?,
         );
 
+        let (width, height): (u32, u32) = self.window.inner_size().into();
+        self.camera.viewport = Vector2::new(width as f32, height as f32);
+
         self.window.request_redraw();
 
         Ok(())
     }
 
-    pub fn update(
-        &mut self,
-        vehicles:   &[Vehicle],
-        tiles:      &TileMap<Tile>,
-        stop_signs: &[StopSign],
-        stoplights: &[Stoplight],
-    ) -> Result<()> {
-        let canvas = self.surface.canvas();
-        let offset = self.gl_surface.height().unwrap() as f32 / 4.0;
-
-        let mut paint = Paint::default();
-        paint.set_color(Color::BLUE);
-
-        canvas.clear(Color::WHITE);
-
-        for tile in &tiles.tiles {
-            let pos = Point::new(tile.pos.x as f32 + 0.5, tile.pos.y as f32);
-
-            let img_pos = Point::new(
-                (pos.x * TILE_SIZE_F - (TILE_SIZE_F / 2.0)) * SCALE,
-                (pos.y * TILE_SIZE_F - (TILE_SIZE_F / 2.0)) * SCALE + offset,
-            );
-
-            let rot_pos = Point::new(
-                img_pos.x + ((TILE_SIZE_F / 2.0) * SCALE),
-                img_pos.y + ((TILE_SIZE_F / 2.0) * SCALE),
-            );
-
-            let (img, offset) = self.loader.get_tile(&tile.dir);
-            let deg = tile.dir.degrees() + offset;
-
-            canvas.rotate(deg, Some(rot_pos));
-            canvas.draw_image(img, img_pos, None);
-            canvas.rotate(-deg, Some(rot_pos));
-        }
-
-        for stop_sign in stop_signs {
-            let img_pos = Point::new(
-                ((stop_sign.pos.x as f32 - 0.5) * TILE_SIZE_F) * SCALE,
-                ((stop_sign.pos.y as f32 - 1.0) * TILE_SIZE_F) * SCALE + offset,
-            );
-
-            canvas.draw_image(&self.loader.stop_signs, img_pos, None);
-        }
-
-        for stoplight in stoplights {
-            let pos = Point::new(
-                stoplight.pos.x as f32 - 0.5,
-                stoplight.pos.y as f32 - 1.0,
-            );
-
-            let img_pos = Point::new(
-                (pos.x * TILE_SIZE_F) * SCALE,
-                (pos.y * TILE_SIZE_F) * SCALE + offset,
-            );
-
-            let rot_pos = Point::new(
-                img_pos.x + (TILE_SIZE_F * SCALE),
-                img_pos.y + (TILE_SIZE_F * SCALE),
-            );
-
-            let img = &self.loader.get_stoplight(&stoplight);
-            let deg = stoplight.axis.degrees();
-
-            canvas.rotate(deg, Some(rot_pos));
-            canvas.draw_image(img, img_pos, None);
-            canvas.rotate(-deg, Some(rot_pos));
-        }
+    pub fn set_title(&self, title: &str) {
+        self.window.set_title(title);
+    }
 
-        for vehicle in vehicles {
-            let pos = vehicle.pos;
+    pub fn world(&self, x: f64, y: f64) -> Point2<f32> {
+        self.camera.to_world(Point2::new(x as f32, y as f32))
+    }
+}
 
-            let img_pos = Point::new(
-                pos.x * SCALE,
-                (pos.y - (TILE_SIZE_F / 2.0)) * SCALE + offset
-            );
+impl Frontend for Renderer {
+    fn camera(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
 
-            let rot_pos = Point::new(
-                img_pos.x + ((TILE_SIZE_F / 2.0) * SCALE),
-                img_pos.y + ((TILE_SIZE_F / 2.0) * SCALE),
-            );
+    fn draw(&mut self, world: &World) -> Result<()> {
+        self.camera.track(world.vehicles);
 
-            canvas.rotate(vehicle.dir.degrees(), Some(rot_pos));
-            canvas.draw_image(&self.loader.car, img_pos, None);
-            canvas.rotate(-vehicle.dir.degrees(), Some(rot_pos));
-        }
+        scene::draw(self.surface.canvas(), &self.loader, &self.camera, world);
+        self.hud.draw(self.surface.canvas(), self.camera.viewport.x);
+        self.inspector.draw(self.surface.canvas(), self.camera.viewport.x);
 
         self.gr_context.flush_and_submit();
         self.gl_surface.swap_buffers(&self.gl_context)?;
@@ -0,0 +1,202 @@
+use crate::camera::Camera;
+use crate::debug;
+use crate::frontend::World;
+use crate::texture_loader::TextureLoader;
+
+use routing::ascii_map::Marker;
+use routing::editor::Issue;
+use routing::priority_sign::Priority;
+use routing::stoplight::Signal;
+use routing::tile_map::TILE_SIZE_F;
+use routing::traffic_control::ControlState;
+
+use skia_safe::{Canvas, Color, Paint, PaintStyle, Point, Rect};
+
+use nalgebra::Point2;
+
+use std::collections::HashMap;
+
+pub struct Overlay<'a> {
+    pub issues:  &'a [Issue],
+    pub markers: &'a HashMap<Point2<i32>, Marker>,
+    pub cursor:  Option<Point2<i32>>,
+}
+
+pub fn draw(canvas: &Canvas, loader: &TextureLoader, camera: &Camera, world: &World) {
+    let World { vehicles, tiles, controls, overlay, debug, .. } = *world;
+
+    canvas.clear(Color::WHITE);
+
+    canvas.save();
+    canvas.translate((camera.viewport.x / 2.0, camera.viewport.y / 2.0));
+    canvas.scale((camera.zoom, camera.zoom));
+    canvas.translate((-camera.center.x, -camera.center.y));
+
+    for tile in &tiles.tiles {
+        let rot_pos = Point::new(
+            tile.pos.x as f32 * TILE_SIZE_F,
+            tile.pos.y as f32 * TILE_SIZE_F,
+        );
+
+        let sprite = loader.tile(&tile.dir);
+        let deg = tile.dir.degrees() + sprite.rotation;
+
+        canvas.rotate(deg, Some(rot_pos));
+        canvas.draw_image(&sprite.image, sprite.origin(rot_pos), None);
+        canvas.rotate(-deg, Some(rot_pos));
+    }
+
+    for control in controls {
+        match control.state() {
+            ControlState::StopSign { center, .. } => {
+                let sprite = loader.stop_signs();
+
+                let pos = Point::new(
+                    center.x * TILE_SIZE_F,
+                    center.y * TILE_SIZE_F,
+                );
+
+                canvas.draw_image(&sprite.image, sprite.origin(pos), None);
+            }
+
+            ControlState::PrioritySign { priority, lines } => {
+                let mut paint = Paint::default();
+                paint.set_anti_alias(true);
+                paint.set_color(
+                    match priority {
+                        Priority::Stop  => Color::RED,
+                        Priority::Yield => Color::YELLOW,
+                    }
+                );
+
+                for line in lines {
+                    let center = Point::new(
+                        (line.0.x + line.1.x) / 2.0,
+                        (line.0.y + line.1.y) / 2.0,
+                    );
+
+                    canvas.draw_circle(center, TILE_SIZE_F / 4.0, &paint);
+                }
+            }
+
+            ControlState::Stoplight { heads, .. } => {
+                let mut housing = Paint::default();
+                housing.set_anti_alias(true);
+                housing.set_color(Color::DARK_GRAY);
+
+                let mut lamp = Paint::default();
+                lamp.set_anti_alias(true);
+
+                let radius = TILE_SIZE_F / 10.0;
+
+                for head in heads {
+                    let center = Point::new(head.line.1.x, head.line.1.y);
+
+                    if let Some(sprite) = loader.signal(head.signal) {
+                        let deg = head.dir.degrees(None) + sprite.rotation;
+
+                        canvas.rotate(deg, Some(center));
+                        canvas.draw_image(&sprite.image, sprite.origin(center), None);
+                        canvas.rotate(-deg, Some(center));
+
+                        continue;
+                    }
+
+                    let rect = Rect::from_xywh(
+                        center.x - radius * 1.5,
+                        center.y - radius * 4.5,
+                        radius * 3.0,
+                        radius * 9.0,
+                    );
+
+                    canvas.draw_round_rect(rect, radius, radius, &housing);
+
+                    let signals = [Signal::Red, Signal::Yellow, Signal::Green];
+
+                    for (idx, signal) in signals.into_iter().enumerate() {
+                        let color =
+                            match signal {
+                                Signal::Red    => Color::RED,
+                                Signal::Yellow => Color::YELLOW,
+                                Signal::Green  => Color::GREEN,
+                            };
+
+                        if signal == head.signal {
+                            lamp.set_color(color);
+                        } else {
+                            lamp.set_color(Color::from_rgb(60, 60, 60));
+                        }
+
+                        let lamp_pos = Point::new(
+                            center.x,
+                            center.y + (idx as f32 - 1.0) * radius * 3.0,
+                        );
+
+                        canvas.draw_circle(lamp_pos, radius, &lamp);
+                    }
+                }
+            }
+        }
+    }
+
+    for vehicle in vehicles {
+        let pos = vehicle.center();
+
+        let rot_pos = Point::new(pos.x, pos.y);
+
+        let sprite = loader.vehicle(vehicle);
+        let deg = vehicle.dir.degrees() + sprite.rotation;
+
+        canvas.rotate(deg, Some(rot_pos));
+        canvas.draw_image(&sprite.image, sprite.origin(rot_pos), None);
+        canvas.rotate(-deg, Some(rot_pos));
+    }
+
+    if debug {
+        debug::draw(canvas, camera, world);
+    }
+
+    if let Some(overlay) = overlay {
+        let tile_rect = |pos: Point2<i32>| {
+            Rect::from_xywh(
+                (pos.x as f32 - 0.5) * TILE_SIZE_F,
+                (pos.y as f32 - 0.5) * TILE_SIZE_F,
+                TILE_SIZE_F,
+                TILE_SIZE_F,
+            )
+        };
+
+        let mut issue = Paint::default();
+        issue.set_color(Color::from_argb(96, 255, 0, 0));
+
+        for pos in overlay.issues.iter().map(|issue| issue.pos) {
+            canvas.draw_rect(tile_rect(pos), &issue);
+        }
+
+        let mut marker = Paint::default();
+        marker.set_anti_alias(true);
+
+        for (&pos, kind) in overlay.markers {
+            marker.set_color(
+                match kind {
+                    Marker::StopSign  => Color::RED,
+                    Marker::Stoplight => Color::GREEN,
+                }
+            );
+
+            canvas.draw_circle(tile_rect(pos).center(), TILE_SIZE_F / 8.0, &marker);
+        }
+
+        if let Some(pos) = overlay.cursor {
+            let mut cursor = Paint::default();
+            cursor.set_anti_alias(true);
+            cursor.set_style(PaintStyle::Stroke);
+            cursor.set_stroke_width(4.0 / camera.zoom);
+            cursor.set_color(Color::BLUE);
+
+            canvas.draw_rect(tile_rect(pos), &cursor);
+        }
+    }
+
+    canvas.restore();
+}
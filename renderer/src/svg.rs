@@ -0,0 +1,404 @@
+use crate::camera::Camera;
+use crate::frontend::{Frontend, World};
+
+use routing::direction::{Cardinal, Direction, TileDirection};
+use routing::intersection::Intersection;
+use routing::priority_sign::Priority;
+use routing::segment_bounds::SegmentBounds;
+use routing::stoplight::Signal;
+use routing::tile_map::TILE_SIZE_F;
+use routing::traffic_control::ControlState;
+
+use nalgebra::{Point2, Vector2};
+use anyhow::{Context, Result};
+
+use std::f32::consts::PI;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const BACKGROUND:   &str = "#f4f4f0";
+pub const ROAD:         &str = "#5a5a5a";
+pub const JUNCTION:     &str = "#6e6e6e";
+pub const ARROW:        &str = "#e8e8e8";
+pub const OUTLINE:      &str = "#f2c230";
+pub const VEHICLE:      &str = "#2f7fd8";
+pub const SIGN_RED:     &str = "#c8102e";
+pub const SIGN_WHITE:   &str = "#ffffff";
+pub const SIGNAL_RED:   &str = "#e0302a";
+pub const SIGNAL_AMBER: &str = "#f0b020";
+pub const SIGNAL_GREEN: &str = "#2cb34a";
+
+const ARROW_LENGTH: f32 = 0.3;
+const SIGN_RADIUS:  f32 = 2.5;
+const HEAD_RADIUS:  f32 = 1.5;
+const AXIS_LENGTH:  f32 = 0.6;
+
+pub struct Svg {
+    pub camera:      Camera,
+    pub speed_scale: Option<f32>,
+
+    document: String,
+}
+
+impl Svg {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            camera:      Camera::new(Vector2::new(width as f32, height as f32)),
+            speed_scale: None,
+            document:    String::new(),
+        }
+    }
+
+    pub fn document(&self) -> &str {
+        &self.document
+    }
+
+    pub fn save<P: AsRef<Path>>(&mut self, path: P, world: &World) -> Result<()> {
+        let path = path.as_ref();
+
+        self.draw(world)?;
+
+        std::fs::write(path, &self.document)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    fn compose(&self, world: &World) -> Result<String> {
+        let mut out = String::new();
+
+        let origin = self.camera.to_world(Point2::origin());
+        let size   = self.camera.viewport / self.camera.zoom;
+
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+            self.camera.viewport.x, self.camera.viewport.y, origin.x, origin.y, size.x, size.y,
+        )?;
+
+        writeln!(out, "<defs>")?;
+        writeln!(
+            out,
+            r#"<marker id="arrow" viewBox="0 0 10 10" refX="5" refY="5" markerWidth="3" markerHeight="3" orient="auto-start-reverse"><path d="M 0 0 L 10 5 L 0 10 z" fill="{ARROW}"/></marker>"#,
+        )?;
+
+        if self.speed_scale.is_some() {
+            writeln!(
+                out,
+                r#"<linearGradient id="speed"><stop offset="0" stop-color="{}"/><stop offset="0.5" stop-color="{}"/><stop offset="1" stop-color="{}"/></linearGradient>"#,
+                speed_color(0.0), speed_color(0.5), speed_color(1.0),
+            )?;
+        }
+
+        writeln!(out, "</defs>")?;
+
+        writeln!(
+            out,
+            r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{BACKGROUND}"/>"#,
+            origin.x, origin.y, size.x, size.y,
+        )?;
+
+        self.tiles(&mut out, world)?;
+        self.intersections(&mut out, world)?;
+        self.controls(&mut out, world)?;
+        self.vehicles(&mut out, world)?;
+
+        if let Some(max) = self.speed_scale {
+            legend(&mut out, origin, size, max)?;
+        }
+
+        writeln!(out, "</svg>")?;
+
+        Ok(out)
+    }
+
+    fn tiles(&self, out: &mut String, world: &World) -> Result<()> {
+        let half = TILE_SIZE_F / 2.0;
+
+        writeln!(out, r#"<g id="tiles" stroke="none">"#)?;
+
+        for tile in &world.tiles.tiles {
+            let center = tile_center(tile.pos);
+
+            let fill =
+                match tile.dir {
+                    TileDirection::Constant(_)     => ROAD,
+                    TileDirection::Intersection(_) => JUNCTION,
+                };
+
+            writeln!(
+                out,
+                r#"<rect x="{:.2}" y="{:.2}" width="{TILE_SIZE_F}" height="{TILE_SIZE_F}" fill="{fill}"/>"#,
+                center.x - half, center.y - half,
+            )?;
+        }
+
+        writeln!(out, "</g>")?;
+
+        writeln!(
+            out,
+            r#"<g id="arrows" fill="none" stroke="{ARROW}" stroke-width="0.6" marker-end="url(#arrow)">"#,
+        )?;
+
+        for tile in &world.tiles.tiles {
+            let center = tile_center(tile.pos);
+            let reach  = TILE_SIZE_F * ARROW_LENGTH;
+
+            match tile.dir {
+                TileDirection::Constant(Direction::Straight(dir)) => {
+                    let from = center - dir.vector() * reach;
+                    let to   = center + dir.vector() * reach;
+
+                    writeln!(out, r#"<path d="M {} L {}"/>"#, coords(from), coords(to))?;
+                }
+
+                TileDirection::Constant(Direction::Turn(in_dir, out_dir)) => {
+                    let from = center - in_dir.vector() * reach;
+                    let to   = center + out_dir.vector() * reach;
+
+                    writeln!(out, r#"<path d="M {} Q {} {}"/>"#, coords(from), coords(center), coords(to))?;
+                }
+
+                TileDirection::Intersection(_) => {}
+            }
+        }
+
+        writeln!(out, "</g>")?;
+
+        Ok(())
+    }
+
+    fn intersections(&self, out: &mut String, world: &World) -> Result<()> {
+        let half = TILE_SIZE_F / 2.0;
+
+        writeln!(
+            out,
+            r#"<g id="intersections" fill="none" stroke="{OUTLINE}" stroke-width="0.8" stroke-dasharray="2 1">"#,
+        )?;
+
+        for intersection in Intersection::all(world.tiles) {
+            let mut path = String::new();
+
+            for &pos in &intersection.tiles {
+                for side in Cardinal::ALL {
+                    if intersection.contains(pos + side.offset()) {
+                        continue;
+                    }
+
+                    let normal = side.vector();
+                    let across = Vector2::new(-normal.y, normal.x);
+                    let edge   = tile_center(pos) + normal * half;
+
+                    write!(
+                        path,
+                        "M {} L {} ",
+                        coords(edge - across * half),
+                        coords(edge + across * half),
+                    )?;
+                }
+            }
+
+            writeln!(out, r#"<path d="{}"/>"#, path.trim_end())?;
+        }
+
+        writeln!(out, "</g>")?;
+
+        Ok(())
+    }
+
+    fn controls(&self, out: &mut String, world: &World) -> Result<()> {
+        writeln!(out, r#"<g id="controls">"#)?;
+
+        for control in world.controls {
+            match control.state() {
+                ControlState::StopSign { lines, .. } => {
+                    for line in &lines {
+                        stop_line(out, line, SIGN_WHITE)?;
+                        octagon(out, midpoint(line))?;
+                    }
+                }
+
+                ControlState::PrioritySign { priority, lines } => {
+                    for line in &lines {
+                        stop_line(out, line, SIGN_WHITE)?;
+
+                        match priority {
+                            Priority::Stop  => octagon(out, midpoint(line))?,
+                            Priority::Yield => triangle(out, midpoint(line))?,
+                        }
+                    }
+                }
+
+                ControlState::Stoplight { heads, .. } => {
+                    for head in heads {
+                        let color =
+                            match head.signal {
+                                Signal::Red    => SIGNAL_RED,
+                                Signal::Yellow => SIGNAL_AMBER,
+                                Signal::Green  => SIGNAL_GREEN,
+                            };
+
+                        let center = midpoint(&head.line);
+
+                        stop_line(out, &head.line, color)?;
+
+                        if head.signal != Signal::Red {
+                            let to = center + head.dir.vector() * TILE_SIZE_F * AXIS_LENGTH;
+
+                            writeln!(
+                                out,
+                                r#"<path d="M {} L {}" stroke="{color}" stroke-width="0.8" stroke-dasharray="1.5 1"/>"#,
+                                coords(center), coords(to),
+                            )?;
+                        }
+
+                        writeln!(
+                            out,
+                            r#"<circle cx="{:.2}" cy="{:.2}" r="{HEAD_RADIUS}" fill="{color}" stroke="black" stroke-width="0.3"/>"#,
+                            center.x, center.y,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        writeln!(out, "</g>")?;
+
+        Ok(())
+    }
+
+    fn vehicles(&self, out: &mut String, world: &World) -> Result<()> {
+        writeln!(out, r#"<g id="vehicles" stroke="black" stroke-width="0.3">"#)?;
+
+        for vehicle in world.vehicles {
+            let points =
+                vehicle
+                    .bounds()
+                    .corners()
+                    .iter()
+                    .map(|&corner| coords(corner))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+            let fill =
+                match self.speed_scale {
+                    Some(max) => speed_color(vehicle.speed() / max.max(f32::EPSILON)),
+                    None      => String::from(VEHICLE),
+                };
+
+            writeln!(
+                out,
+                r#"<polygon id="vehicle-{}" points="{points}" fill="{fill}"><title>vehicle {} at {:.1}</title></polygon>"#,
+                vehicle.id, vehicle.id, vehicle.speed(),
+            )?;
+        }
+
+        writeln!(out, "</g>")?;
+
+        Ok(())
+    }
+}
+
+impl Frontend for Svg {
+    fn camera(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    fn draw(&mut self, world: &World) -> Result<()> {
+        self.camera.track(world.vehicles);
+
+        self.document = self.compose(world)?;
+
+        Ok(())
+    }
+}
+
+pub fn speed_color(fraction: f32) -> String {
+    let fraction = fraction.clamp(0.0, 1.0);
+
+    let (red, green) =
+        if fraction < 0.5 {
+            (1.0, fraction * 2.0)
+        } else {
+            ((1.0 - fraction) * 2.0, 1.0)
+        };
+
+    format!("#{:02x}{:02x}30", (red * 220.0) as u8, (green * 200.0) as u8)
+}
+
+fn legend(out: &mut String, origin: Point2<f32>, size: Vector2<f32>, max: f32) -> Result<()> {
+    let pad    = size.y * 0.03;
+    let width  = size.x * 0.25;
+    let height = size.y * 0.025;
+
+    let (x, y) = (origin.x + pad, origin.y + pad);
+
+    writeln!(out, r#"<g id="legend" font-family="sans-serif" font-size="{height:.2}">"#)?;
+    writeln!(
+        out,
+        r#"<rect x="{x:.2}" y="{y:.2}" width="{width:.2}" height="{height:.2}" fill="url(#speed)" stroke="black" stroke-width="0.2"/>"#,
+    )?;
+    writeln!(out, r#"<text x="{x:.2}" y="{:.2}">0</text>"#, y + height * 2.2)?;
+    writeln!(
+        out,
+        r#"<text x="{:.2}" y="{:.2}" text-anchor="end">{max}</text>"#,
+        x + width, y + height * 2.2,
+    )?;
+    writeln!(out, "</g>")?;
+
+    Ok(())
+}
+
+fn stop_line(out: &mut String, line: &SegmentBounds, color: &str) -> Result<()> {
+    writeln!(
+        out,
+        r#"<path d="M {} L {}" stroke="{color}" stroke-width="0.8"/>"#,
+        coords(line.0), coords(line.1),
+    )?;
+
+    Ok(())
+}
+
+fn octagon(out: &mut String, center: Point2<f32>) -> Result<()> {
+    let points =
+        (0..8)
+            .map(|idx| {
+                let angle = PI / 8.0 + idx as f32 * PI / 4.0;
+                coords(center + Vector2::new(angle.cos(), angle.sin()) * SIGN_RADIUS)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+    writeln!(
+        out,
+        r#"<polygon points="{points}" fill="{SIGN_RED}" stroke="{SIGN_WHITE}" stroke-width="0.4"/>"#,
+    )?;
+
+    Ok(())
+}
+
+fn triangle(out: &mut String, center: Point2<f32>) -> Result<()> {
+    let points =
+        [PI / 2.0, PI * 7.0 / 6.0, PI * 11.0 / 6.0]
+            .iter()
+            .map(|angle| coords(center + Vector2::new(angle.cos(), angle.sin()) * SIGN_RADIUS))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+    writeln!(
+        out,
+        r#"<polygon points="{points}" fill="{SIGN_WHITE}" stroke="{SIGN_RED}" stroke-width="0.6"/>"#,
+    )?;
+
+    Ok(())
+}
+
+fn tile_center(pos: Point2<i32>) -> Point2<f32> {
+    Point2::new(pos.x as f32, pos.y as f32) * TILE_SIZE_F
+}
+
+fn midpoint(line: &SegmentBounds) -> Point2<f32> {
+    Point2::from((line.0.coords + line.1.coords) / 2.0)
+}
+
+fn coords(point: Point2<f32>) -> String {
+    format!("{:.2},{:.2}", point.x, point.y)
+}
@@ -0,0 +1,137 @@
+use crate::camera::Camera;
+use crate::cells::{CellGrid, Tint};
+use crate::frontend::{Frontend, World};
+
+use routing::direction::Cardinal;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use nalgebra::Vector2;
+use anyhow::Result;
+
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Input {
+    Char(char),
+    Arrow(Cardinal),
+    Escape,
+    Quit,
+}
+
+pub struct Terminal {
+    pub camera: Camera,
+    pub status: String,
+
+    stdout: Stdout,
+}
+
+impl Terminal {
+    pub fn new() -> Result<Self> {
+        let (width, height) = terminal::size()?;
+
+        let mut stdout = io::stdout();
+
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide)?;
+
+        Ok(Self {
+            camera: Camera::new(Vector2::new(width as f32, height as f32)),
+            status: String::new(),
+            stdout,
+        })
+    }
+
+    pub fn input(&mut self, timeout: Duration) -> Result<Option<Input>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+
+        let Event::Key(key) = event::read()? else {
+            return Ok(None);
+        };
+
+        if key.kind == KeyEventKind::Release {
+            return Ok(None);
+        }
+
+        let input =
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Input::Quit,
+
+                KeyCode::Char(ch) => Input::Char(ch.to_ascii_lowercase()),
+                KeyCode::Left     => Input::Arrow(Cardinal::Left),
+                KeyCode::Right    => Input::Arrow(Cardinal::Right),
+                KeyCode::Up       => Input::Arrow(Cardinal::Up),
+                KeyCode::Down     => Input::Arrow(Cardinal::Down),
+                KeyCode::Esc      => Input::Escape,
+
+                _ => return Ok(None),
+            };
+
+        Ok(Some(input))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Frontend for Terminal {
+    fn camera(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    fn draw(&mut self, world: &World) -> Result<()> {
+        let (width, height) = terminal::size()?;
+
+        self.camera.viewport = Vector2::new(width as f32, height as f32);
+        self.camera.track(world.vehicles);
+
+        let rows = (height as usize).saturating_sub(1);
+
+        let mut grid = CellGrid::compose(world, self.camera.center, width as usize, rows + 1);
+        grid.text(0, rows as i32, &format!("{:<1$}", self.status, width as usize), Tint::Plain);
+
+        let mut tint = None;
+
+        for (row, cells) in grid.rows().enumerate() {
+            queue!(self.stdout, MoveTo(0, row as u16))?;
+
+            for cell in cells {
+                if tint != Some(cell.tint) {
+                    queue!(self.stdout, SetForegroundColor(color(cell.tint)))?;
+                    tint = Some(cell.tint);
+                }
+
+                queue!(self.stdout, Print(cell.glyph))?;
+            }
+        }
+
+        queue!(self.stdout, ResetColor)?;
+
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+}
+
+fn color(tint: Tint) -> Color {
+    match tint {
+        Tint::Plain    => Color::Reset,
+        Tint::Road     => Color::DarkGrey,
+        Tint::Junction => Color::Grey,
+        Tint::Red      => Color::Red,
+        Tint::Yellow   => Color::Yellow,
+        Tint::Green    => Color::Green,
+        Tint::Vehicle  => Color::Cyan,
+    }
+}
@@ -1,91 +1,314 @@
-use crate::renderer::SCALE;
-use routing::direction::{Cardinal, Direction, TileDirection};
+use routing::direction::{Direction, TileDirection};
+use routing::stoplight::Signal;
+use routing::vehicle::Vehicle;
 
 use image::{EncodableLayout, ImageBuffer, Rgba};
-use image::imageops::{self, FilterType};
-use routing::stoplight::{Stoplight, GRACE_TIME};
-use skia_safe::{images, AlphaType, ColorType, Image, ImageInfo};
+use skia_safe::{images, AlphaType, ColorType, Image, ImageInfo, Point};
+use serde_json::Value;
 
-use anyhow::{Result, Context};
+use anyhow::{bail, Context, Result};
 
-pub struct TextureLoader {
-    pub car: Image,
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+pub const MANIFEST:        &str     = "pack.json";
+pub const DEFAULT_VEHICLE: &str     = "car";
+pub const TRUCK_LENGTH:    f32      = 12.0;
+pub const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+const EMBEDDED_MANIFEST: &str = include_str!("assets/pack.json");
+
+const EMBEDDED: [(&str, &[u8]); 5] = [
+    ("car.png",          include_bytes!("assets/car.png")),
+    ("straight.png",     include_bytes!("assets/straight.png")),
+    ("turn.png",         include_bytes!("assets/turn.png")),
+    ("intersection.png", include_bytes!("assets/intersection.png")),
+    ("stop-signs.png",   include_bytes!("assets/stop-signs.png")),
+];
+
+const SIGNALS: [(&str, Signal); 3] = [
+    ("red",    Signal::Red),
+    ("yellow", Signal::Yellow),
+    ("green",  Signal::Green),
+];
+
+#[derive(Clone)]
+pub struct Sprite {
+    pub image:    Image,
+    pub anchor:   Point,
+    pub rotation: f32,
+}
+
+impl Sprite {
+    pub fn origin(&self, pos: Point) -> Point {
+        pos - self.anchor
+    }
+}
+
+#[derive(Clone)]
+pub struct AssetPack {
+    pub name: String,
+
+    straight:     Sprite,
+    turn_left:    Sprite,
+    turn_right:   Sprite,
+    intersection: Sprite,
+
+    vehicles: HashMap<String, Sprite>,
+    signals:  Vec<(Signal, Sprite)>,
+
+    pub stop_signs: Sprite,
+}
+
+impl AssetPack {
+    pub fn embedded() -> Result<Self> {
+        let read = |name: &str| {
+            EMBEDDED
+                .iter()
+                .find(|(file, _)| *file == name)
+                .map(|(_, data)| data.to_vec())
+                .with_context(|| format!("no embedded asset {name:?}"))
+        };
+
+        Self::build(&parse(EMBEDDED_MANIFEST)?, &read, None)
+    }
+
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir  = dir.as_ref();
+        let path = dir.join(MANIFEST);
+
+        let text =
+            fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+
+        let manifest = parse(&text).with_context(|| format!("in {}", path.display()))?;
+
+        let read = |name: &str| {
+            let path = dir.join(name);
 
-    straight:     Image,
-    turn:         Image,
-    intersection: Image,
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+        };
 
-    pub stop_signs: Image,
+        Self::build(&manifest, &read, Some(Self::embedded()?))
+    }
+
+    fn build(manifest: &Value, read: &dyn Fn(&str) -> Result<Vec<u8>>, base: Option<Self>) -> Result<Self> {
+        let slot = |value: &Value, fallback: Option<&Sprite>, what: &str| {
+            sprite(value, read)
+                .with_context(|| format!("in {what}"))?
+                .or_else(|| fallback.cloned())
+                .with_context(|| format!("asset pack has no {what}"))
+        };
+
+        let tiles = &manifest["tiles"];
+
+        let name =
+            manifest["name"]
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| base.as_ref().map(|base| base.name.clone()))
+                .unwrap_or_default();
+
+        let mut pack = Self {
+            name,
+            straight:     slot(&tiles["straight"], base.as_ref().map(|base| &base.straight), "straight tile")?,
+            turn_left:    slot(&tiles["turn_left"], base.as_ref().map(|base| &base.turn_left), "left turn tile")?,
+            turn_right:   slot(&tiles["turn_right"], base.as_ref().map(|base| &base.turn_right), "right turn tile")?,
+            intersection: slot(&tiles["intersection"], base.as_ref().map(|base| &base.intersection), "intersection tile")?,
+            stop_signs:   slot(&manifest["stop_sign"], base.as_ref().map(|base| &base.stop_signs), "stop sign")?,
+            vehicles:     base.as_ref().map(|base| base.vehicles.clone()).unwrap_or_default(),
+            signals:      base.as_ref().map(|base| base.signals.clone()).unwrap_or_default(),
+        };
+
+        for (class, value) in manifest["vehicles"].as_object().into_iter().flatten() {
+            let loaded = slot(value, None, &format!("vehicle {class:?}"))?;
+
+            pack.vehicles.insert(class.clone(), loaded);
+        }
+
+        if !pack.vehicles.contains_key(DEFAULT_VEHICLE) {
+            bail!("asset pack has no {DEFAULT_VEHICLE:?} vehicle");
+        }
+
+        for (key, signal) in SIGNALS {
+            if let Some(loaded) = sprite(&manifest["signals"][key], read).with_context(|| format!("in {key} signal"))? {
+                pack.signals.retain(|(existing, _)| *existing != signal);
+                pack.signals.push((signal, loaded));
+            }
+        }
 
-    stoplight:       Image,
-    stoplight_grace: Image,
-    stoplight_stop:  Image,
+        Ok(pack)
+    }
+}
+
+pub struct TextureLoader {
+    pub pack:   AssetPack,
+    pub source: Option<PathBuf>,
+
+    stamp:   Option<SystemTime>,
+    checked: Instant,
 }
 
 impl TextureLoader {
     pub fn new() -> Result<Self> {
         Ok(Self {
-            car: load_image(include_bytes!("assets/car.png"))?,
-
-            straight:     load_image(include_bytes!("assets/straight.png"))?,
-            turn:         load_image(include_bytes!("assets/turn.png"))?,
-            intersection: load_image(include_bytes!("assets/intersection.png"))?,
+            pack:    AssetPack::embedded()?,
+            source:  None,
+            stamp:   None,
+            checked: Instant::now(),
+        })
+    }
 
-            stop_signs: load_image(include_bytes!("assets/stop-signs.png"))?,
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
 
-            stoplight:       load_image(include_bytes!("assets/stoplight.png"))?,
-            stoplight_grace: load_image(include_bytes!("assets/stoplight-grace.png"))?,
-            stoplight_stop:  load_image(include_bytes!("assets/stoplight-stop.png"))?,
+        Ok(Self {
+            pack:    AssetPack::load(&dir)?,
+            stamp:   modified(&dir)?,
+            source:  Some(dir),
+            checked: Instant::now(),
         })
     }
 
-    pub fn get_tile(&self, tile_dir: &TileDirection) -> (&Image, f32) {
-        match tile_dir {
-            TileDirection::Constant(dir) => {
-                match dir {
-                    Direction::Straight(_) => (&self.straight, 0.0),
-
-                    Direction::Turn(Cardinal::Left,  Cardinal::Up)    |
-                    Direction::Turn(Cardinal::Right, Cardinal::Down)  |
-                    Direction::Turn(Cardinal::Up,    Cardinal::Right) |
-                    Direction::Turn(Cardinal::Down,  Cardinal::Left)
-                        => (&self.turn, -45.0 + 180.0),
-
-                    Direction::Turn(_, _) => (&self.turn, -45.0),
-                }
-            }
+    pub fn poll(&mut self) -> Result<bool> {
+        let Some(dir) = &self.source else {
+            return Ok(false);
+        };
+
+        if self.checked.elapsed() < RELOAD_INTERVAL {
+            return Ok(false);
+        }
+
+        self.checked = Instant::now();
+
+        let stamp = modified(dir)?;
 
-            TileDirection::Intersection(_) => (&self.intersection, 0.0),
+        if stamp == self.stamp {
+            return Ok(false);
         }
+
+        self.stamp = stamp;
+        self.pack  = AssetPack::load(dir)?;
+
+        Ok(true)
     }
 
-    pub fn get_stoplight(&self, stoplight: &Stoplight) -> &Image {
-        if let Some(period) = stoplight.grace {
-            if period >= ((GRACE_TIME - 1.0) * 60.0) as u32 {
-                &self.stoplight_stop
-            } else {
-                &self.stoplight_grace
-            }
-        } else {
-            &self.stoplight
+    pub fn tile(&self, tile_dir: &TileDirection) -> &Sprite {
+        match tile_dir {
+            TileDirection::Constant(Direction::Straight(_))     => &self.pack.straight,
+            TileDirection::Constant(dir) if dir.is_right_turn() => &self.pack.turn_right,
+            TileDirection::Constant(_)                          => &self.pack.turn_left,
+            TileDirection::Intersection(_)                      => &self.pack.intersection,
         }
     }
+
+    pub fn vehicle(&self, vehicle: &Vehicle) -> &Sprite {
+        let class =
+            if vehicle.length() >= TRUCK_LENGTH {
+                "truck"
+            } else {
+                DEFAULT_VEHICLE
+            };
+
+        self.pack.vehicles
+            .get(class)
+            .unwrap_or_else(|| &self.pack.vehicles[DEFAULT_VEHICLE])
+    }
+
+    pub fn signal(&self, signal: Signal) -> Option<&Sprite> {
+        self.pack.signals
+            .iter()
+            .find(|(state, _)| *state == signal)
+            .map(|(_, sprite)| sprite)
+    }
+
+    pub fn stop_signs(&self) -> &Sprite {
+        &self.pack.stop_signs
+    }
+}
+
+fn parse(text: &str) -> Result<Value> {
+    let manifest: Value = serde_json::from_str(text).context("invalid asset manifest")?;
+
+    if !manifest.is_object() {
+        bail!("asset manifest must be a JSON object");
+    }
+
+    Ok(manifest)
+}
+
+fn sprite(value: &Value, read: &dyn Fn(&str) -> Result<Vec<u8>>) -> Result<Option<Sprite>> {
+    let (file, entry) =
+        match value {
+            Value::Null           => return Ok(None),
+            Value::String(file)   => (file.as_str(), &Value::Null),
+            Value::Object(object) => {
+                let file =
+                    object.get("image")
+                        .and_then(Value::as_str)
+                        .context("sprite needs an \"image\"")?;
+
+                (file, value)
+            }
+
+            _ => bail!("sprite must be a file name or an object"),
+        };
+
+    let image = load_image(&read(file)?).with_context(|| format!("failed to decode {file}"))?;
+
+    let anchor =
+        match &entry["anchor"] {
+            Value::Null => Point::new(image.width() as f32 / 2.0, image.height() as f32 / 2.0),
+
+            Value::Array(coords) if coords.len() == 2 => {
+                let coord = |idx: usize| {
+                    coords[idx]
+                        .as_f64()
+                        .map(|coord| coord as f32)
+                        .context("anchor coordinates must be numbers")
+                };
+
+                Point::new(coord(0)?, coord(1)?)
+            }
+
+            _ => bail!("anchor must be [x, y] in pixels"),
+        };
+
+    let rotation =
+        match &entry["rotation"] {
+            Value::Null => 0.0,
+
+            rotation => rotation.as_f64().context("rotation must be a number of degrees")? as f32,
+        };
+
+    Ok(Some(Sprite {
+        image,
+        anchor,
+        rotation,
+    }))
+}
+
+fn modified(dir: &Path) -> Result<Option<SystemTime>> {
+    let entries =
+        fs::read_dir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?;
+
+    let stamp =
+        entries
+            .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+            .max();
+
+    Ok(stamp)
 }
 
 fn load_image(data: &[u8]) -> Result<Image> {
     let img =
-        image::load_from_memory(data)
-            .unwrap()
+        image::load_from_memory(data)?
             .to_rgba8();
 
-    let resized = imageops::resize(
-        &img,
-        img.width()  * SCALE as u32,
-        img.height() * SCALE as u32,
-        FilterType::Nearest,
-    );
-
-    skia_image(&resized)
+    skia_image(&img)
 }
 
 fn skia_image(img: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<Image> {
@@ -0,0 +1,344 @@
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::intersection::Intersection;
+use crate::road_grid;
+use crate::stop_sign::StopSign;
+use crate::stoplight::Stoplight;
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+use crate::traffic_control::TrafficControl;
+
+use parry2d::na::Point2;
+use anyhow::{bail, Context, Result};
+
+use std::collections::HashMap;
+use std::fmt;
+
+pub const STOPLIGHT_FREQ: f32 = 10.0;
+
+const EMPTY:        char = ' ';
+const INTERSECTION: char = '+';
+const STOP_SIGN:    char = 'S';
+const STOPLIGHT:    char = 'L';
+const TABLE:        char = '@';
+
+const STRAIGHTS: [(char, Cardinal); 4] = [
+    ('^', Cardinal::Up),
+    ('v', Cardinal::Down),
+    ('<', Cardinal::Left),
+    ('>', Cardinal::Right),
+];
+
+const TURNS: [(char, Cardinal, Cardinal); 8] = [
+    ('┐', Cardinal::Right, Cardinal::Down),
+    ('┘', Cardinal::Down,  Cardinal::Left),
+    ('└', Cardinal::Left,  Cardinal::Up),
+    ('┌', Cardinal::Up,    Cardinal::Right),
+    ('╮', Cardinal::Up,    Cardinal::Left),
+    ('╯', Cardinal::Right, Cardinal::Up),
+    ('╰', Cardinal::Down,  Cardinal::Right),
+    ('╭', Cardinal::Left,  Cardinal::Down),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Marker {
+    StopSign,
+    Stoplight,
+}
+
+pub struct AsciiMap {
+    pub tiles:   TileMap<Tile>,
+    pub markers: HashMap<Point2<i32>, Marker>,
+}
+
+impl AsciiMap {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut cells   = HashMap::new();
+        let mut markers = HashMap::new();
+
+        let rows = text.lines().filter(|line| !line.starts_with(TABLE));
+
+        for (y, line) in rows.enumerate() {
+            for (x, symbol) in line.chars().enumerate() {
+                let pos = Point2::new(x as i32, y as i32);
+
+                let cell =
+                    match symbol {
+                        EMPTY | '.'  => continue,
+                        INTERSECTION => None,
+
+                        STOP_SIGN => {
+                            markers.insert(pos, Marker::StopSign);
+                            None
+                        }
+
+                        STOPLIGHT => {
+                            markers.insert(pos, Marker::Stoplight);
+                            None
+                        }
+
+                        _ => match direction(symbol) {
+                            Some(dir) => Some(dir),
+                            None      => bail!("unknown map symbol {symbol:?} at line {}, column {}", y + 1, x + 1),
+                        },
+                    };
+
+                cells.insert(pos, cell);
+            }
+        }
+
+        let mut tiles  = road_grid::resolve(&cells)?;
+        let mut tables = HashMap::<_, HashMap<_, _>>::new();
+
+        for line in text.lines().filter(|line| line.starts_with(TABLE)) {
+            let (pos, heading, outs) = table_entry(line).with_context(|| format!("malformed turn table line {line:?}"))?;
+
+            tables.entry(pos).or_default().insert(Direction::Straight(heading), outs);
+        }
+
+        for tile in &mut tiles.tiles {
+            let Some(table) = tables.remove(&tile.pos) else {
+                continue;
+            };
+
+            match &mut tile.dir {
+                TileDirection::Intersection(dirs) => *dirs = table,
+                TileDirection::Constant(_)        => bail!("turn table at {:?} is not on an intersection", tile.pos),
+            }
+        }
+
+        if let Some(pos) = tables.keys().next() {
+            bail!("turn table at {pos:?} is not on a road tile");
+        }
+
+        Ok(Self { tiles, markers })
+    }
+
+    pub fn controls(&self) -> Result<Vec<Box<dyn TrafficControl>>> {
+        controls(&self.tiles, &self.markers)
+    }
+}
+
+impl fmt::Display for AsciiMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", print(&self.tiles, &self.markers))
+    }
+}
+
+pub fn controls(tiles: &TileMap<Tile>, markers: &HashMap<Point2<i32>, Marker>) -> Result<Vec<Box<dyn TrafficControl>>> {
+    let mut controls: Vec<Box<dyn TrafficControl>> = Vec::new();
+
+    for intersection in Intersection::all(tiles) {
+        let mut found =
+            intersection.tiles
+                .iter()
+                .filter_map(|pos| markers.get(pos).copied())
+                .collect::<Vec<_>>();
+
+        found.dedup();
+
+        match found.as_slice() {
+            [] => {}
+
+            [Marker::StopSign] => controls.push(Box::new(StopSign::new(intersection))),
+
+            [Marker::Stoplight] => controls.push(Box::new(Stoplight::new(intersection, STOPLIGHT_FREQ)?)),
+
+            _ => bail!("intersection at {:?} has mixed control markers", intersection.tiles[0]),
+        }
+    }
+
+    Ok(controls)
+}
+
+pub fn print(tiles: &TileMap<Tile>, markers: &HashMap<Point2<i32>, Marker>) -> String {
+    let Some(first) = tiles.tiles.first() else {
+        return String::new();
+    };
+
+    let (min, max) =
+        tiles.tiles
+            .iter()
+            .fold((first.pos, first.pos), |(min, max), tile| {
+                (
+                    Point2::new(min.x.min(tile.pos.x), min.y.min(tile.pos.y)),
+                    Point2::new(max.x.max(tile.pos.x), max.y.max(tile.pos.y)),
+                )
+            });
+
+    let mut text = String::new();
+
+    for y in min.y..=max.y {
+        let line =
+            (min.x..=max.x)
+                .map(|x| {
+                    let pos = Point2::new(x, y);
+
+                    tiles.get(&pos)
+                        .map(|tile| symbol(tile, markers.get(&pos)))
+                        .unwrap_or(EMPTY)
+                })
+                .collect::<String>();
+
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+
+    let cells =
+        tiles.tiles
+            .iter()
+            .map(|tile| match tile.dir {
+                TileDirection::Constant(dir)   => (tile.pos, Some(dir)),
+                TileDirection::Intersection(_) => (tile.pos, None),
+            })
+            .collect::<HashMap<_, _>>();
+
+    let inferred = road_grid::resolve(&cells).ok();
+
+    let mut custom =
+        tiles.tiles
+            .iter()
+            .filter_map(|tile| match &tile.dir {
+                TileDirection::Intersection(table) => Some((tile.pos, table)),
+                TileDirection::Constant(_)         => None,
+            })
+            .filter(|&(pos, table)| {
+                let default =
+                    inferred
+                        .as_ref()
+                        .and_then(|inferred| inferred.get(&pos))
+                        .map(|tile| &tile.dir);
+
+                !matches!(default, Some(TileDirection::Intersection(other)) if other == table)
+            })
+            .collect::<Vec<_>>();
+
+    custom.sort_by_key(|(pos, _)| (pos.y, pos.x));
+
+    for (pos, table) in custom {
+        for heading in Cardinal::ALL {
+            let Some(outs) = table.get(&Direction::Straight(heading)) else {
+                continue;
+            };
+
+            let outs = outs.iter().map(|&out| straight(out)).collect::<String>();
+
+            text.push_str(&format!("{TABLE}{},{} {} {outs}\n", pos.x - min.x, pos.y - min.y, straight(heading)));
+        }
+    }
+
+    text
+}
+
+fn direction(symbol: char) -> Option<Direction> {
+    cardinal(symbol)
+        .map(Direction::Straight)
+        .or_else(|| {
+            TURNS
+                .iter()
+                .find(|(turn, _, _)| *turn == symbol)
+                .map(|&(_, in_dir, out_dir)| Direction::Turn(in_dir, out_dir))
+        })
+}
+
+fn symbol(tile: &Tile, marker: Option<&Marker>) -> char {
+    match (&tile.dir, marker) {
+        (TileDirection::Intersection(_), None)                      => INTERSECTION,
+        (TileDirection::Intersection(_), Some(Marker::StopSign))    => STOP_SIGN,
+        (TileDirection::Intersection(_), Some(Marker::Stoplight))   => STOPLIGHT,
+
+        (TileDirection::Constant(Direction::Turn(in_dir, out_dir)), _) => {
+            TURNS
+                .iter()
+                .find(|(_, turn_in, turn_out)| turn_in == in_dir && turn_out == out_dir)
+                .map(|&(turn, _, _)| turn)
+                .unwrap_or(INTERSECTION)
+        }
+
+        (TileDirection::Constant(Direction::Straight(dir)), _) => straight(*dir),
+    }
+}
+
+fn straight(dir: Cardinal) -> char {
+    STRAIGHTS
+        .iter()
+        .find(|(_, straight)| *straight == dir)
+        .map(|&(symbol, _)| symbol)
+        .unwrap_or(INTERSECTION)
+}
+
+fn cardinal(symbol: char) -> Option<Cardinal> {
+    STRAIGHTS
+        .iter()
+        .find(|(straight, _)| *straight == symbol)
+        .map(|&(_, dir)| dir)
+}
+
+fn table_entry(line: &str) -> Option<(Point2<i32>, Cardinal, Vec<Cardinal>)> {
+    let mut parts = line[TABLE.len_utf8()..].split_whitespace();
+
+    let (x, y) = parts.next()?.split_once(',')?;
+
+    let mut heading = parts.next()?.chars();
+    let dir = cardinal(heading.next()?).filter(|_| heading.next().is_none())?;
+
+    let outs =
+        parts
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(cardinal)
+            .collect::<Option<Vec<_>>>()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((Point2::new(x.parse().ok()?, y.parse().ok()?), dir, outs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CROSSING: &str = "  v^
+  v^
+<<L+<<
+>>++>>
+  v^
+  v^
+@2,2 < v
+";
+
+    const CORNER: &str = ">>┐
+  v
+  ╰>
+";
+
+    #[test]
+    fn round_trips_through_print() {
+        let map = AsciiMap::parse(CROSSING).unwrap();
+
+        assert_eq!(map.to_string(), CROSSING);
+        assert_eq!(AsciiMap::parse(CORNER).unwrap().to_string(), CORNER);
+    }
+
+    #[test]
+    fn applies_markers_and_tables() {
+        let map = AsciiMap::parse(CROSSING).unwrap();
+        let pos = Point2::new(2, 2);
+
+        let Some(TileDirection::Intersection(table)) = map.tiles.get(&pos).map(|tile| &tile.dir) else {
+            panic!("expected an intersection at {pos:?}");
+        };
+
+        assert_eq!(table.get(&Direction::Straight(Cardinal::Left)), Some(&vec![Cardinal::Down]));
+        assert_eq!(map.markers.get(&pos), Some(&Marker::Stoplight));
+        assert_eq!(map.controls().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_symbols() {
+        assert!(AsciiMap::parse(">>x>").is_err());
+        assert!(AsciiMap::parse(">>>\n@0,0 > v").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use parry2d::query;
 use parry2d::math::Isometry;
 use parry2d::shape::Shape;
+use parry2d::na::Point2;
 
 pub trait Bounds {
     fn as_parry(&self) -> (Isometry<f32>, Box<dyn Shape>);
@@ -12,4 +13,10 @@ pub trait Bounds {
         query::contact(&iso, &*shape, &other_iso, &*other_shape, 0.0)
             .is_ok_and(|target| target.is_some())
     }
+
+    fn contains(&self, point: &Point2<f32>) -> bool {
+        let (iso, shape) = self.as_parry();
+
+        shape.contains_point(&iso, point)
+    }
 }
@@ -0,0 +1,343 @@
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+
+use parry2d::na::Point2;
+
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Conflict {
+    Crossing,
+    Merging,
+    Diverging,
+}
+
+#[derive(Debug, Clone)]
+pub struct Movement {
+    pub entry: Cardinal,
+    pub exit:  Cardinal,
+    pub tiles: Vec<Point2<i32>>,
+}
+
+impl Movement {
+    pub fn start(&self) -> Point2<i32> {
+        self.tiles[0]
+    }
+
+    pub fn end(&self) -> Point2<i32> {
+        self.tiles[self.tiles.len() - 1]
+    }
+
+    pub fn turn(&self) -> Direction {
+        Direction::Straight(self.entry).towards(self.exit)
+    }
+
+    fn conflict(&self, other: &Movement) -> Option<Conflict> {
+        let shared =
+            self.tiles
+                .iter()
+                .any(|tile| other.tiles.contains(tile));
+
+        if self.start() == other.start() && self.entry == other.entry {
+            Some(Conflict::Diverging)
+        } else if self.end() == other.end() && self.exit == other.exit {
+            Some(Conflict::Merging)
+        } else if shared {
+            Some(Conflict::Crossing)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct ConflictMatrix {
+    pub tiles:     Vec<Point2<i32>>,
+    pub movements: Vec<Movement>,
+    conflicts:     Vec<Vec<Option<Conflict>>>,
+}
+
+impl ConflictMatrix {
+    pub fn new(tiles: &TileMap<Tile>, cluster: Vec<Point2<i32>>) -> Self {
+        let movements =
+            entries(tiles, &cluster)
+                .into_iter()
+                .flat_map(|(pos, dir)| movements(tiles, &cluster, pos, dir))
+                .collect::<Vec<_>>();
+
+        let conflicts =
+            movements
+                .iter()
+                .enumerate()
+                .map(|(idx, movement)| {
+                    movements
+                        .iter()
+                        .enumerate()
+                        .map(|(other_idx, other)| {
+                            if idx == other_idx {
+                                None
+                            } else {
+                                movement.conflict(other)
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+
+        Self {
+            tiles: cluster,
+            movements,
+            conflicts,
+        }
+    }
+
+    pub fn all(tiles: &TileMap<Tile>) -> Vec<Self> {
+        clusters(tiles)
+            .into_iter()
+            .map(|cluster| Self::new(tiles, cluster))
+            .collect()
+    }
+
+    pub fn contains(&self, pos: &Point2<i32>) -> bool {
+        self.tiles.contains(pos)
+    }
+
+    pub fn conflict(&self, movement: usize, other: usize) -> Option<Conflict> {
+        self.conflicts[movement][other]
+    }
+
+    pub fn conflicting(&self, movement: usize, other: usize) -> bool {
+        matches!(
+            self.conflict(movement, other),
+            Some(Conflict::Crossing | Conflict::Merging),
+        )
+    }
+
+    pub fn compatible(&self, movements: &[usize]) -> bool {
+        movements.iter().all(|&movement| {
+            movements
+                .iter()
+                .all(|&other| !self.conflicting(movement, other))
+        })
+    }
+
+    pub fn from_approach(&self, entry: Cardinal) -> Vec<usize> {
+        self.movements
+            .iter()
+            .enumerate()
+            .filter(|(_, movement)| movement.entry == entry)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    pub fn approaches_conflict(&self, entry: Cardinal, other: Cardinal) -> bool {
+        let others = self.from_approach(other);
+
+        self.from_approach(entry).iter().any(|&movement| {
+            others
+                .iter()
+                .any(|&other| self.conflicting(movement, other))
+        })
+    }
+
+    pub fn phase_conflicts(&self, entries: &[Cardinal]) -> Vec<(usize, usize)> {
+        let movements =
+            entries
+                .iter()
+                .flat_map(|&entry| self.from_approach(entry))
+                .collect::<Vec<_>>();
+
+        movements
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &movement)| {
+                movements[idx + 1..]
+                    .iter()
+                    .filter(move |&&other| self.conflicting(movement, other))
+                    .map(move |&other| (movement, other))
+            })
+            .collect()
+    }
+}
+
+pub fn clusters(tiles: &TileMap<Tile>) -> Vec<Vec<Point2<i32>>> {
+    let mut clusters = Vec::new();
+    let mut seen     = HashSet::new();
+
+    for tile in &tiles.tiles {
+        if seen.contains(&tile.pos) || !is_intersection(tiles, &tile.pos) {
+            continue;
+        }
+
+        seen.insert(tile.pos);
+
+        let mut cluster = vec![tile.pos];
+        let mut open    = vec![tile.pos];
+
+        while let Some(pos) = open.pop() {
+            for dir in Cardinal::ALL {
+                let next = pos + dir.offset();
+
+                if is_intersection(tiles, &next) && seen.insert(next) {
+                    cluster.push(next);
+                    open.push(next);
+                }
+            }
+        }
+
+        clusters.push(cluster);
+    }
+
+    clusters
+}
+
+fn is_intersection(tiles: &TileMap<Tile>, pos: &Point2<i32>) -> bool {
+    tiles
+        .get(pos)
+        .is_some_and(|tile| matches!(tile.dir, TileDirection::Intersection(_)))
+}
+
+pub fn entries(tiles: &TileMap<Tile>, cluster: &[Point2<i32>]) -> Vec<(Point2<i32>, Direction)> {
+    cluster
+        .iter()
+        .flat_map(|&pos| {
+            Cardinal::ALL
+                .into_iter()
+                .filter_map(move |dir| {
+                    let tile = tiles.get(&(pos - dir.offset()))?;
+
+                    match tile.dir {
+                        TileDirection::Constant(tile_dir) if tile_dir.out_dir() == dir => {
+                            Some((pos, tile_dir))
+                        }
+                        _ => None,
+                    }
+                })
+        })
+        .collect()
+}
+
+pub fn movements(
+    tiles:   &TileMap<Tile>,
+    cluster: &[Point2<i32>],
+    pos:     Point2<i32>,
+    dir:     Direction,
+) -> Vec<Movement> {
+    let entry = dir.out_dir();
+
+    let mut found = Vec::new();
+    let mut open  = vec![(pos, dir, vec![pos])];
+
+    while let Some((pos, dir, path)) = open.pop() {
+        let Some(tile) = tiles.get(&pos) else {
+            continue;
+        };
+
+        for out_dir in tile.dir.choices(dir) {
+            let next     = pos + out_dir.offset();
+            let next_dir = dir.towards(out_dir);
+
+            if !cluster.contains(&next) {
+                found.push(Movement {
+                    entry,
+                    exit:  out_dir,
+                    tiles: path.clone(),
+                });
+            } else if !path.contains(&next) {
+                let mut path = path.clone();
+                path.push(next);
+
+                open.push((next, next_dir, path));
+            }
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::direction::Cardinal::*;
+
+    use std::collections::HashMap;
+
+    fn straight(x: i32, y: i32, dir: Cardinal) -> Tile {
+        Tile::new(Point2::new(x, y), TileDirection::Constant(Direction::Straight(dir)))
+    }
+
+    fn junction(x: i32, y: i32, table: HashMap<Direction, Vec<Cardinal>>) -> Tile {
+        Tile::new(Point2::new(x, y), TileDirection::Intersection(table))
+    }
+
+    fn movement(matrix: &ConflictMatrix, entry: Cardinal, exit: Cardinal) -> usize {
+        matrix.movements
+            .iter()
+            .position(|movement| movement.entry == entry && movement.exit == exit)
+            .expect("movement not found")
+    }
+
+    #[test]
+    fn classifies_single_tile_conflicts() {
+        let tiles = TileMap::new(vec![
+            straight(-1,  0, Right),
+            straight( 1,  0, Right),
+            straight( 0, -1, Down),
+            straight( 0,  1, Down),
+
+            junction(0, 0, HashMap::from([
+                (Direction::Straight(Right), vec![Right, Down]),
+                (Direction::Straight(Down),  vec![Down]),
+            ])),
+        ]);
+
+        let matrix = ConflictMatrix::new(&tiles, vec![Point2::new(0, 0)]);
+
+        assert_eq!(matrix.movements.len(), 3);
+
+        let through = movement(&matrix, Right, Right);
+        let turn    = movement(&matrix, Right, Down);
+        let down    = movement(&matrix, Down, Down);
+
+        assert_eq!(matrix.conflict(through, turn), Some(Conflict::Diverging));
+        assert_eq!(matrix.conflict(turn, down),    Some(Conflict::Merging));
+        assert_eq!(matrix.conflict(through, down), Some(Conflict::Crossing));
+        assert_eq!(matrix.conflict(down, down),    None);
+
+        assert!(!matrix.conflicting(through, turn));
+        assert!(matrix.conflicting(turn, down));
+        assert!(matrix.compatible(&[through, turn]));
+        assert!(!matrix.compatible(&[through, down]));
+        assert!(matrix.approaches_conflict(Right, Down));
+    }
+
+    #[test]
+    fn clusters_adjacent_intersections() {
+        let tiles = TileMap::new(vec![
+            straight(-1, 0, Right),
+            straight( 2, 0, Right),
+            straight( 2, 1, Left),
+            straight(-1, 1, Left),
+
+            junction(0, 0, HashMap::from([(Direction::Straight(Right), vec![Right])])),
+            junction(1, 0, HashMap::from([(Direction::Straight(Right), vec![Right])])),
+            junction(1, 1, HashMap::from([(Direction::Straight(Left),  vec![Left])])),
+            junction(0, 1, HashMap::from([(Direction::Straight(Left),  vec![Left])])),
+        ]);
+
+        let clusters = clusters(&tiles);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), 4);
+
+        let matrix = ConflictMatrix::new(&tiles, clusters[0].clone());
+
+        let east = movement(&matrix, Right, Right);
+        let west = movement(&matrix, Left, Left);
+
+        assert_eq!(matrix.movements[east].tiles, vec![Point2::new(0, 0), Point2::new(1, 0)]);
+        assert_eq!(matrix.conflict(east, west), None);
+        assert!(matrix.phase_conflicts(&[Right, Left]).is_empty());
+    }
+}
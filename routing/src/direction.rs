@@ -1,3 +1,5 @@
+use crate::random;
+
 use parry2d::na::Vector2;
 use rand::distributions::{Distribution, WeightedIndex};
 
@@ -11,14 +13,47 @@ pub enum TileDirection {
 }
 
 impl TileDirection {
+    pub fn choices(&self, cur_dir: Direction) -> Vec<Cardinal> {
+        match self {
+            Self::Constant(dir) => vec![dir.out_dir()],
+            Self::Intersection(dirs) => {
+                if let Some(possible) = dirs.get(&cur_dir) {
+                    possible.clone()
+                } else {
+                    let out_dir = cur_dir.out_dir();
+
+                    dirs.get(&Direction::Straight(out_dir))
+                        .filter(|possible| possible.contains(&out_dir))
+                        .map(|_| vec![out_dir])
+                        .unwrap_or_default()
+                }
+            }
+        }
+    }
+
     pub fn as_dir(&self, cur_dir: Direction) -> Direction {
+        self.choose(cur_dir, |_| true)
+    }
+
+    pub fn choose<F>(&self, cur_dir: Direction, filter: F) -> Direction
+    where
+        F: Fn(Cardinal) -> bool,
+    {
         match self {
             Self::Constant(dir) => *dir,
-            Self::Intersection(dirs) => {
-                let possible = &dirs[&cur_dir];
+            Self::Intersection(_) => {
+                let mut possible = self.choices(cur_dir);
+
+                if possible.iter().any(|&dir| filter(dir)) {
+                    possible.retain(|&dir| filter(dir));
+                }
 
                 let out_dir = cur_dir.out_dir();
 
+                if possible.is_empty() {
+                    return Direction::Straight(out_dir);
+                }
+
                 let weights =
                     possible
                         .iter()
@@ -26,15 +61,8 @@ impl TileDirection {
                         .collect::<Vec<_>>();
 
                 let dist = WeightedIndex::new(&weights).unwrap();
-                let mut rng = rand::thread_rng();
-
-                let new_dir = possible[dist.sample(&mut rng)];
 
-                if new_dir == out_dir {
-                    Direction::Straight(new_dir)
-                } else {
-                    Direction::Turn(out_dir, new_dir)
-                }
+                cur_dir.towards(possible[random::with(|rng| dist.sample(rng))])
             }
         }
     }
@@ -68,6 +96,24 @@ impl Direction {
         }
     }
 
+    pub fn towards(&self, new_dir: Cardinal) -> Self {
+        let out_dir = self.out_dir();
+
+        if new_dir == out_dir {
+            Self::Straight(new_dir)
+        } else {
+            Self::Turn(out_dir, new_dir)
+        }
+    }
+
+    pub fn is_left_turn(&self) -> bool {
+        matches!(self, Self::Turn(in_dir, out_dir) if *out_dir == in_dir.counter_rotate())
+    }
+
+    pub fn is_right_turn(&self) -> bool {
+        matches!(self, Self::Turn(in_dir, out_dir) if out_dir.counter_rotate() == *in_dir)
+    }
+
     pub fn vector(&self) -> Vector2<f32> {
         match self {
             Self::Straight(dir) => dir.vector(),
@@ -96,6 +142,13 @@ pub enum Cardinal {
 }
 
 impl Cardinal {
+    pub const ALL: [Self; 4] = [
+        Self::Up,
+        Self::Right,
+        Self::Down,
+        Self::Left,
+    ];
+
     pub fn rotate(&mut self) -> Self {
         match self {
             Self::Up    => Self::Right,
@@ -105,6 +158,24 @@ impl Cardinal {
         }
     }
 
+    pub fn counter_rotate(&self) -> Self {
+        match self {
+            Self::Up    => Self::Left,
+            Self::Left  => Self::Down,
+            Self::Down  => Self::Right,
+            Self::Right => Self::Up,
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up    => Self::Down,
+            Self::Down  => Self::Up,
+            Self::Left  => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
     pub fn vector(&self) -> Vector2<f32> {
         match self {
             Self::Up    => Vector2::new( 0.0, -1.0),
@@ -114,6 +185,15 @@ impl Cardinal {
         }
     }
 
+    pub fn offset(&self) -> Vector2<i32> {
+        match self {
+            Self::Up    => Vector2::new( 0, -1),
+            Self::Down  => Vector2::new( 0,  1),
+            Self::Left  => Vector2::new(-1,  0),
+            Self::Right => Vector2::new( 1,  0),
+        }
+    }
+
     pub fn degrees(&self, other: Option<&Cardinal>) -> f32 {
         match self {
             Self::Up    => if other.is_some_and(|&dir| dir == Cardinal::Left) { 360.0 } else { 0.0 },
@@ -0,0 +1,493 @@
+use crate::ascii_map::{self, AsciiMap, Marker};
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::grid::Grid;
+use crate::intersection::Intersection;
+use crate::raster;
+use crate::road_grid;
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+
+use parry2d::na::Point2;
+use anyhow::{Context, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const HISTORY: usize = 256;
+
+type Table = HashMap<Direction, Vec<Cardinal>>;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tool {
+    Straight,
+    Turn,
+    LeftTurn,
+    Intersection,
+    StopSign,
+    Stoplight,
+    TurnTable,
+    Erase,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Problem {
+    Unconnected,
+    Mismatch,
+    NoExit,
+    StrayMarker,
+    MixedMarkers,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Issue {
+    pub pos:     Point2<i32>,
+    pub problem: Problem,
+}
+
+#[derive(Default, Clone, PartialEq)]
+struct Layout {
+    cells:   HashMap<Point2<i32>, Option<Direction>>,
+    markers: HashMap<Point2<i32>, Marker>,
+    tables:  HashMap<Point2<i32>, Table>,
+}
+
+pub struct Editor {
+    pub tool:     Tool,
+    pub rotation: Cardinal,
+
+    layout: Layout,
+    before: Option<Layout>,
+    undo:   Vec<Layout>,
+    redo:   Vec<Layout>,
+    stroke: Option<Point2<i32>>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self {
+            tool:     Tool::Straight,
+            rotation: Cardinal::Up,
+            layout:   Layout::default(),
+            before:   None,
+            undo:     Vec::new(),
+            redo:     Vec::new(),
+            stroke:   None,
+        }
+    }
+
+    pub fn from_tiles(tiles: &TileMap<Tile>, markers: &HashMap<Point2<i32>, Marker>) -> Self {
+        let mut editor = Self::new();
+
+        editor.layout.markers = markers.clone();
+        editor.layout.cells =
+            tiles.tiles
+                .iter()
+                .map(|tile| match tile.dir {
+                    TileDirection::Constant(dir)   => (tile.pos, Some(dir)),
+                    TileDirection::Intersection(_) => (tile.pos, None),
+                })
+                .collect();
+
+        let inferred = editor.tiles().ok();
+
+        for tile in &tiles.tiles {
+            let TileDirection::Intersection(table) = &tile.dir else {
+                continue;
+            };
+
+            let default =
+                inferred
+                    .as_ref()
+                    .and_then(|inferred| inferred.get(&tile.pos))
+                    .map(|tile| &tile.dir);
+
+            if !matches!(default, Some(TileDirection::Intersection(other)) if other == table) {
+                editor.layout.tables.insert(tile.pos, table.clone());
+            }
+        }
+
+        editor
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path).context("failed to read map file")?;
+        let map  = AsciiMap::parse(&text)?;
+
+        Ok(Self::from_tiles(&map.tiles, &map.markers))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.text()?).context("failed to write map file")
+    }
+
+    pub fn text(&self) -> Result<String> {
+        Ok(ascii_map::print(&self.tiles()?, &self.layout.markers))
+    }
+
+    pub fn markers(&self) -> &HashMap<Point2<i32>, Marker> {
+        &self.layout.markers
+    }
+
+    pub fn tiles(&self) -> Result<TileMap<Tile>> {
+        let cells =
+            self.layout.cells
+                .iter()
+                .filter(|&(&pos, cell)| cell.is_some() || !road_grid::crossings(&self.layout.cells, pos).is_empty())
+                .map(|(&pos, &cell)| (pos, cell))
+                .collect::<HashMap<_, _>>();
+
+        if cells.is_empty() {
+            return Ok(TileMap::new(Vec::new()));
+        }
+
+        let mut tiles = road_grid::resolve(&cells)?;
+
+        for tile in &mut tiles.tiles {
+            if let (TileDirection::Intersection(dirs), Some(table)) = (&mut tile.dir, self.layout.tables.get(&tile.pos)) {
+                *dirs = table.clone();
+            }
+        }
+
+        Ok(tiles)
+    }
+
+    pub fn grid(&self) -> Result<Grid> {
+        let tiles    = self.tiles()?;
+        let controls = ascii_map::controls(&tiles, &self.layout.markers)?;
+
+        Ok(Grid::new(tiles, controls))
+    }
+
+    pub fn begin(&mut self, pos: Point2<i32>) {
+        self.end();
+
+        self.before = Some(self.layout.clone());
+        self.stroke = Some(pos);
+        self.apply(pos);
+    }
+
+    pub fn drag(&mut self, pos: Point2<i32>) {
+        let Some(last) = self.stroke else {
+            return;
+        };
+
+        for (from, to) in raster::line(last, pos) {
+            if self.tool == Tool::Straight {
+                self.rotation = raster::heading(from, to);
+
+                if let Some(Some(Direction::Straight(_))) = self.layout.cells.get(&from) {
+                    self.layout.cells.insert(from, Some(Direction::Straight(self.rotation)));
+                }
+            }
+
+            self.stroke = Some(to);
+            self.apply(to);
+        }
+    }
+
+    pub fn end(&mut self) {
+        self.stroke = None;
+
+        if let Some(before) = self.before.take().filter(|before| *before != self.layout) {
+            self.checkpoint_with(before);
+        }
+    }
+
+    pub fn rotate_at(&mut self, pos: Point2<i32>) {
+        let Some(Some(dir)) = self.layout.cells.get(&pos).copied() else {
+            return;
+        };
+
+        let rotate = |mut cardinal: Cardinal| cardinal.rotate();
+
+        let rotated =
+            match dir {
+                Direction::Straight(dir)         => Direction::Straight(rotate(dir)),
+                Direction::Turn(in_dir, out_dir) => Direction::Turn(rotate(in_dir), rotate(out_dir)),
+            };
+
+        self.checkpoint();
+        self.layout.cells.insert(pos, Some(rotated));
+    }
+
+    pub fn toggle_turn(&mut self, pos: Point2<i32>, heading: Cardinal, out: Cardinal) -> Result<()> {
+        let tiles = self.tiles()?;
+
+        let Some(TileDirection::Intersection(table)) = tiles.get(&pos).map(|tile| &tile.dir) else {
+            return Ok(());
+        };
+
+        let mut table = table.clone();
+        let outs      = table.entry(Direction::Straight(heading)).or_default();
+
+        if let Some(idx) = outs.iter().position(|&dir| dir == out) {
+            outs.remove(idx);
+        } else {
+            outs.push(out);
+        }
+
+        self.checkpoint();
+        self.layout.tables.insert(pos, table);
+
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(layout) = self.undo.pop() else {
+            return false;
+        };
+
+        self.redo.push(std::mem::replace(&mut self.layout, layout));
+
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(layout) = self.redo.pop() else {
+            return false;
+        };
+
+        self.undo.push(std::mem::replace(&mut self.layout, layout));
+
+        true
+    }
+
+    pub fn validate(&self) -> Result<Vec<Issue>> {
+        let tiles = self.tiles()?;
+
+        let mut issues = Vec::new();
+
+        let mut report = |pos: Point2<i32>, problem: Problem| {
+            issues.push(Issue { pos, problem });
+        };
+
+        for (&pos, cell) in &self.layout.cells {
+            if cell.is_none() && tiles.get(&pos).is_none() {
+                report(pos, Problem::Unconnected);
+            }
+        }
+
+        for tile in &tiles.tiles {
+            let outs =
+                match &tile.dir {
+                    TileDirection::Constant(dir) => vec![dir.out_dir()],
+
+                    TileDirection::Intersection(table) => {
+                        if table.values().any(|outs| outs.is_empty()) {
+                            report(tile.pos, Problem::NoExit);
+                        }
+
+                        table.values().flatten().copied().collect()
+                    }
+                };
+
+            let broken =
+                outs.into_iter().any(|out| {
+                    tiles
+                        .get(&(tile.pos + out.offset()))
+                        .is_some_and(|next| !accepts(next, out))
+                });
+
+            if broken {
+                report(tile.pos, Problem::Mismatch);
+            }
+        }
+
+        for &pos in self.layout.markers.keys() {
+            if !matches!(tiles.get(&pos).map(|tile| &tile.dir), Some(TileDirection::Intersection(_))) {
+                report(pos, Problem::StrayMarker);
+            }
+        }
+
+        for intersection in Intersection::all(&tiles) {
+            let mut found =
+                intersection.tiles
+                    .iter()
+                    .filter_map(|pos| self.layout.markers.get(pos))
+                    .collect::<Vec<_>>();
+
+            found.dedup();
+
+            if found.len() > 1 {
+                for &pos in &intersection.tiles {
+                    report(pos, Problem::MixedMarkers);
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+
+    fn checkpoint(&mut self) {
+        self.checkpoint_with(self.layout.clone());
+    }
+
+    fn checkpoint_with(&mut self, layout: Layout) {
+        self.undo.push(layout);
+        self.redo.clear();
+
+        if self.undo.len() > HISTORY {
+            self.undo.remove(0);
+        }
+    }
+
+    fn apply(&mut self, pos: Point2<i32>) {
+        let mut rotation = self.rotation;
+
+        match self.tool {
+            Tool::Straight     => self.place(pos, Some(Direction::Straight(rotation))),
+            Tool::Turn         => self.place(pos, Some(Direction::Turn(rotation, rotation.rotate()))),
+            Tool::LeftTurn     => self.place(pos, Some(Direction::Turn(rotation, rotation.counter_rotate()))),
+            Tool::Intersection => self.place(pos, None),
+            Tool::StopSign     => self.mark(pos, Marker::StopSign),
+            Tool::Stoplight    => self.mark(pos, Marker::Stoplight),
+            Tool::TurnTable    => {}
+            Tool::Erase        => self.remove(pos),
+        }
+    }
+
+    fn place(&mut self, pos: Point2<i32>, cell: Option<Direction>) {
+        if self.layout.cells.insert(pos, cell) == Some(cell) {
+            return;
+        }
+
+        self.layout.tables.remove(&pos);
+
+        if cell.is_some() {
+            self.layout.markers.remove(&pos);
+        }
+    }
+
+    fn mark(&mut self, pos: Point2<i32>, marker: Marker) {
+        if self.layout.cells.get(&pos) != Some(&None) {
+            return;
+        }
+
+        if self.layout.markers.get(&pos) == Some(&marker) {
+            self.layout.markers.remove(&pos);
+        } else {
+            self.layout.markers.insert(pos, marker);
+        }
+    }
+
+    fn remove(&mut self, pos: Point2<i32>) {
+        self.layout.cells.remove(&pos);
+        self.layout.markers.remove(&pos);
+        self.layout.tables.remove(&pos);
+    }
+}
+
+fn accepts(tile: &Tile, heading: Cardinal) -> bool {
+    match &tile.dir {
+        TileDirection::Constant(dir)       => dir.in_dir() == heading,
+        TileDirection::Intersection(table) => table.contains_key(&Direction::Straight(heading)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paint(editor: &mut Editor, tool: Tool, cells: &[(i32, i32)]) {
+        editor.tool = tool;
+
+        for (idx, &(x, y)) in cells.iter().enumerate() {
+            if idx == 0 {
+                editor.begin(Point2::new(x, y));
+            } else {
+                editor.drag(Point2::new(x, y));
+            }
+        }
+
+        editor.end();
+    }
+
+    fn problems(editor: &Editor) -> Vec<(Point2<i32>, Problem)> {
+        let mut problems =
+            editor.validate()
+                .unwrap()
+                .into_iter()
+                .map(|issue| (issue.pos, issue.problem))
+                .collect::<Vec<_>>();
+
+        problems.sort_by_key(|&(pos, _)| (pos.y, pos.x));
+        problems
+    }
+
+    #[test]
+    fn undoes_and_redoes_strokes() {
+        let mut editor = Editor::new();
+
+        paint(&mut editor, Tool::Straight, &[(0, 0), (1, 0), (2, 0)]);
+        paint(&mut editor, Tool::Erase, &[(1, 0)]);
+
+        assert_eq!(editor.text().unwrap(), "> >\n");
+
+        assert!(editor.undo());
+        assert_eq!(editor.text().unwrap(), ">>>\n");
+
+        assert!(editor.undo());
+        assert_eq!(editor.text().unwrap(), "");
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert_eq!(editor.text().unwrap(), ">>>\n");
+
+        paint(&mut editor, Tool::Straight, &[(2, 1), (2, 2)]);
+
+        assert!(!editor.redo());
+        assert_eq!(editor.text().unwrap(), ">>>\n  v\n  v\n");
+    }
+
+    #[test]
+    fn fills_skipped_cells_while_dragging() {
+        let mut editor = Editor::new();
+
+        paint(&mut editor, Tool::Straight, &[(0, 0), (3, 0)]);
+
+        assert_eq!(editor.text().unwrap(), ">>>>\n");
+
+        paint(&mut editor, Tool::Straight, &[(0, 2), (2, 4)]);
+
+        assert_eq!(editor.text().unwrap(), ">>>>\n\nv\n>v\n >>\n");
+
+        assert!(editor.undo());
+        assert_eq!(editor.text().unwrap(), ">>>>\n");
+    }
+
+    #[test]
+    fn reports_layout_problems() {
+        let map     = AsciiMap::parse(">><<\n").unwrap();
+        let markers = HashMap::from([(Point2::new(0, 0), Marker::StopSign)]);
+
+        let mut editor = Editor::from_tiles(&map.tiles, &markers);
+
+        paint(&mut editor, Tool::Intersection, &[(6, 3)]);
+
+        assert_eq!(problems(&editor), [
+            (Point2::new(0, 0), Problem::StrayMarker),
+            (Point2::new(1, 0), Problem::Mismatch),
+            (Point2::new(2, 0), Problem::Mismatch),
+            (Point2::new(6, 3), Problem::Unconnected),
+        ]);
+
+        assert!(editor.undo());
+        assert_eq!(problems(&editor).len(), 3);
+    }
+
+    #[test]
+    fn accepts_a_clean_crossing() {
+        let map    = AsciiMap::parse("  v\n>>L>>\n  v\n").unwrap();
+        let editor = Editor::from_tiles(&map.tiles, &map.markers);
+
+        assert!(problems(&editor).is_empty());
+        assert_eq!(editor.grid().unwrap().controls.len(), 1);
+    }
+}
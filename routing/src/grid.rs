@@ -0,0 +1,307 @@
+use crate::ascii_map::STOPLIGHT_FREQ;
+use crate::conflict;
+use crate::direction::{Cardinal, TileDirection};
+use crate::intersection::Intersection;
+use crate::road_grid::RoadGrid;
+use crate::stop_sign::StopSign;
+use crate::stoplight::Stoplight;
+use crate::tile::Tile;
+use crate::tile_map::{TileMap, TILE_SIZE_F};
+use crate::traffic_control::{ControlState, TrafficControl};
+use crate::vehicle::Vehicle;
+
+use parry2d::na::Point2;
+use anyhow::{bail, Result};
+
+pub const BLOCK_SIZE: i32 = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Streets {
+    OneWay,
+    TwoWay,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ControlPolicy {
+    Uncontrolled,
+    StopSigns,
+    Stoplights(f32),
+    Checkerboard(f32),
+}
+
+pub struct Grid {
+    pub tiles:    TileMap<Tile>,
+    pub controls: Vec<Box<dyn TrafficControl>>,
+
+    pub entries:  Vec<Point2<f32>>,
+    pub exits:    Vec<Point2<i32>>,
+    pub clusters: Vec<Vec<Point2<i32>>>,
+}
+
+impl Grid {
+    pub fn new(tiles: TileMap<Tile>, controls: Vec<Box<dyn TrafficControl>>) -> Self {
+        let mut entries = Vec::new();
+        let mut exits   = Vec::new();
+
+        for tile in &tiles.tiles {
+            let TileDirection::Constant(dir) = tile.dir else {
+                continue;
+            };
+
+            if tiles.get(&(tile.pos - dir.in_dir().offset())).is_none() {
+                entries.push(Point2::new(tile.pos.x as f32, tile.pos.y as f32) * TILE_SIZE_F);
+            }
+
+            if tiles.get(&(tile.pos + dir.out_dir().offset())).is_none() {
+                exits.push(tile.pos);
+            }
+        }
+
+        Self {
+            clusters: conflict::clusters(&tiles),
+            tiles,
+            controls,
+            entries,
+            exits,
+        }
+    }
+
+    pub fn cluster(&self, pos: Point2<i32>) -> Option<&[Point2<i32>]> {
+        self.clusters
+            .iter()
+            .find(|cluster| cluster.contains(&pos))
+            .map(Vec::as_slice)
+    }
+
+    pub fn exited(&self, vehicle: &Vehicle) -> bool {
+        self.exits.contains(&vehicle.tile_pos)
+    }
+
+    pub fn control_at(&mut self, pos: Point2<i32>) -> Option<&mut Box<dyn TrafficControl>> {
+        self.controls
+            .iter_mut()
+            .find(|control| control.intersection().contains(pos))
+    }
+
+    pub fn cycle_control(&mut self, pos: Point2<i32>) -> Result<()> {
+        let intersection = Intersection::new(&self.tiles, pos)?;
+
+        let current =
+            self.controls
+                .iter()
+                .position(|control| control.intersection().contains(pos));
+
+        let next: Option<Box<dyn TrafficControl>> =
+            match current.map(|idx| self.controls[idx].state()) {
+                None => Some(Box::new(Stoplight::new(intersection, STOPLIGHT_FREQ)?)),
+
+                Some(ControlState::Stoplight { .. }) => Some(Box::new(StopSign::new(intersection))),
+
+                Some(ControlState::StopSign { .. } | ControlState::PrioritySign { .. }) => None,
+            };
+
+        if let Some(idx) = current {
+            self.controls.remove(idx);
+        }
+
+        self.controls.extend(next);
+
+        Ok(())
+    }
+}
+
+pub struct GridGenerator {
+    pub cols: usize,
+    pub rows: usize,
+
+    pub block_size: i32,
+    pub streets:    Streets,
+    pub policy:     ControlPolicy,
+}
+
+impl GridGenerator {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            block_size: BLOCK_SIZE,
+            streets:    Streets::TwoWay,
+            policy:     ControlPolicy::Stoplights(10.0),
+        }
+    }
+
+    fn width(&self) -> i32 {
+        match self.streets {
+            Streets::OneWay => 1,
+            Streets::TwoWay => 2,
+        }
+    }
+
+    fn pitch(&self) -> i32 {
+        self.block_size + self.width()
+    }
+
+    fn lanes(&self, idx: usize, forward: Cardinal) -> Vec<(i32, Cardinal)> {
+        match self.streets {
+            Streets::TwoWay => {
+                vec![(0, forward), (1, forward.opposite())]
+            }
+
+            Streets::OneWay if idx % 2 == 0 => vec![(0, forward)],
+            Streets::OneWay                        => vec![(0, forward.opposite())],
+        }
+    }
+
+    pub fn generate(&self) -> Result<Grid> {
+        if self.cols == 0 || self.rows == 0 {
+            bail!("grid needs at least one block");
+        }
+
+        if self.block_size < 1 {
+            bail!("block size must be positive");
+        }
+
+        let pitch = self.pitch();
+        let stub  = self.block_size;
+
+        let width  = self.cols as i32 * pitch + self.width();
+        let height = self.rows as i32 * pitch + self.width();
+
+        let mut grid    = RoadGrid::new();
+        let mut entries = Vec::new();
+        let mut exits   = Vec::new();
+
+        let mut lane = |grid: &mut RoadGrid, start: Point2<i32>, dir: Cardinal, len: i32| {
+            grid.line(start, dir, len as usize);
+
+            entries.push(Point2::new(start.x as f32, start.y as f32) * TILE_SIZE_F);
+            exits.push(start + dir.offset() * (len - 1));
+        };
+
+        for col in 0..=self.cols {
+            let x   = col as i32 * pitch;
+            let len = height + stub * 2;
+
+            for (offset, dir) in self.lanes(col, Cardinal::Down) {
+                let start =
+                    match dir {
+                        Cardinal::Down => Point2::new(x + offset, -stub),
+                        _              => Point2::new(x + offset, height + stub - 1),
+                    };
+
+                lane(&mut grid, start, dir, len);
+            }
+        }
+
+        for row in 0..=self.rows {
+            let y   = row as i32 * pitch;
+            let len = width + stub * 2;
+
+            for (offset, dir) in self.lanes(row, Cardinal::Left) {
+                let start =
+                    match dir {
+                        Cardinal::Left => Point2::new(width + stub - 1, y + offset),
+                        _              => Point2::new(-stub, y + offset),
+                    };
+
+                lane(&mut grid, start, dir, len);
+            }
+        }
+
+        let tiles = grid.finish();
+
+        let mut controls = Vec::new();
+
+        for intersection in Intersection::all(&tiles) {
+            controls.extend(self.control(intersection)?);
+        }
+
+        Ok(Grid {
+            clusters: conflict::clusters(&tiles),
+            tiles,
+            controls,
+            entries,
+            exits,
+        })
+    }
+
+    fn control(&self, intersection: Intersection) -> Result<Option<Box<dyn TrafficControl>>> {
+        let corner =
+            intersection.tiles
+                .iter()
+                .fold(Point2::new(i32::MAX, i32::MAX), |min, pos| {
+                    Point2::new(min.x.min(pos.x), min.y.min(pos.y))
+                });
+
+        let col = corner.x / self.pitch();
+        let row = corner.y / self.pitch();
+
+        let control: Box<dyn TrafficControl> =
+            match self.policy {
+                ControlPolicy::Uncontrolled => return Ok(None),
+
+                ControlPolicy::StopSigns => Box::new(StopSign::new(intersection)),
+
+                ControlPolicy::Stoplights(freq) => Box::new(Stoplight::new(intersection, freq)?),
+
+                ControlPolicy::Checkerboard(freq) if (col + row) % 2 == 0
+                    => Box::new(Stoplight::new(intersection, freq)?),
+
+                ControlPolicy::Checkerboard(_) => Box::new(StopSign::new(intersection)),
+            };
+
+        Ok(Some(control))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(grid: &Grid, stoplight: bool) -> usize {
+        grid.controls
+            .iter()
+            .filter(|control| matches!(control.state(), ControlState::Stoplight { .. }) == stoplight)
+            .count()
+    }
+
+    #[test]
+    fn generates_two_way_blocks() {
+        let grid = GridGenerator::new(3, 2).generate().unwrap();
+
+        let pitch  = BLOCK_SIZE + 2;
+        let width  = 3 * pitch + 2 + BLOCK_SIZE * 2;
+        let height = 2 * pitch + 2 + BLOCK_SIZE * 2;
+
+        assert_eq!(grid.tiles.tiles.len() as i32, 4 * 2 * height + 3 * 2 * width - 4 * 3 * 4);
+        assert_eq!(grid.clusters.len(), 4 * 3);
+        assert!(grid.clusters.iter().all(|cluster| cluster.len() == 4));
+        assert_eq!(grid.entries.len(), 4 * 2 + 3 * 2);
+        assert_eq!(grid.exits.len(), grid.entries.len());
+        assert_eq!(count(&grid, true), 12);
+    }
+
+    #[test]
+    fn generates_one_way_checkerboard() {
+        let mut generator = GridGenerator::new(2, 2);
+        generator.streets = Streets::OneWay;
+        generator.policy  = ControlPolicy::Checkerboard(10.0);
+
+        let grid = generator.generate().unwrap();
+
+        assert_eq!(grid.clusters.len(), 9);
+        assert!(grid.clusters.iter().all(|cluster| cluster.len() == 1));
+        assert_eq!(grid.entries.len(), 6);
+        assert_eq!(count(&grid, true), 5);
+        assert_eq!(count(&grid, false), 4);
+    }
+
+    #[test]
+    fn leaves_uncontrolled_grids_bare() {
+        let mut generator = GridGenerator::new(1, 1);
+        generator.policy  = ControlPolicy::Uncontrolled;
+
+        assert!(generator.generate().unwrap().controls.is_empty());
+        assert!(GridGenerator::new(0, 3).generate().is_err());
+    }
+}
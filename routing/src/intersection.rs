@@ -0,0 +1,360 @@
+use crate::bounds::Bounds;
+use crate::conflict::{self, ConflictMatrix};
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::segment_bounds::SegmentBounds;
+use crate::tile::Tile;
+use crate::tile_map::{TileMap, TILE_SIZE_F};
+use crate::vehicle::Vehicle;
+
+use parry2d::na::{self, Point2, Vector2};
+use anyhow::{Result, Context};
+
+pub const APPROACH_TILES: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct Approach {
+    pub dir:       Cardinal,
+    pub entry:     Point2<i32>,
+    pub tiles:     Vec<Point2<i32>>,
+    pub stop_line: SegmentBounds,
+}
+
+impl Approach {
+    pub fn new(tiles: &TileMap<Tile>, entry: Point2<i32>, dir: Cardinal) -> Self {
+        let mut lane = Vec::new();
+        let mut pos  = entry - dir.offset();
+
+        while lane.len() < APPROACH_TILES {
+            let Some(tile) = tiles.get(&pos) else {
+                break;
+            };
+
+            match tile.dir {
+                TileDirection::Constant(Direction::Straight(tile_dir)) if tile_dir == dir => {
+                    lane.push(pos);
+                    pos -= dir.offset();
+                }
+
+                TileDirection::Constant(tile_dir) if tile_dir.out_dir() == dir => {
+                    lane.push(pos);
+                    break;
+                }
+
+                _ => break,
+            }
+        }
+
+        let vec  = dir.vector();
+        let perp = Vector2::new(-vec.y, vec.x) * (TILE_SIZE_F / 2.0);
+
+        let mid = (
+            Point2::new(entry.x as f32, entry.y as f32) - (vec / 2.0)
+        ) * TILE_SIZE_F;
+
+        Self {
+            dir,
+            entry,
+            tiles:     lane,
+            stop_line: SegmentBounds(mid - perp, mid + perp),
+        }
+    }
+
+    pub fn midpoint(&self) -> Point2<f32> {
+        na::center(&self.stop_line.0, &self.stop_line.1)
+    }
+
+    pub fn waiting<'a>(&self, vehicles: &'a [Vehicle]) -> Option<&'a Vehicle> {
+        self.tiles.iter().find_map(|pos| {
+            vehicles
+                .iter()
+                .filter(|vehicle| {
+                    vehicle.tile_pos == *pos &&
+                    vehicle.dir.out_dir() == self.dir
+                })
+                .max_by(|a, b| {
+                    let a_dist = a.pos.coords.dot(&self.dir.vector());
+                    let b_dist = b.pos.coords.dot(&self.dir.vector());
+
+                    a_dist.total_cmp(&b_dist)
+                })
+        })
+    }
+
+    pub fn queue(&self, vehicles: &[Vehicle]) -> usize {
+        vehicles
+            .iter()
+            .filter(|vehicle| {
+                self.tiles.contains(&vehicle.tile_pos) &&
+                vehicle.dir.out_dir() == self.dir
+            })
+            .count()
+    }
+
+    pub fn gap(&self, vehicles: &[Vehicle]) -> f32 {
+        let mid = self.midpoint();
+
+        vehicles
+            .iter()
+            .filter_map(|vehicle| {
+                let offset = mid - vehicle.pos;
+
+                let along   = offset.dot(&self.dir.vector());
+                let lateral = offset.perp(&self.dir.vector()).abs();
+
+                let approaching =
+                    vehicle.entry.is_none() &&
+                    vehicle.dir.out_dir() == self.dir &&
+                    along > 0.0 &&
+                    lateral < TILE_SIZE_F / 2.0;
+
+                approaching.then(|| along / vehicle.speed().max(1.0))
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+pub struct Intersection {
+    pub tiles:      Vec<Point2<i32>>,
+    pub approaches: Vec<Approach>,
+    pub matrix:     ConflictMatrix,
+}
+
+impl Intersection {
+    pub fn new(tiles: &TileMap<Tile>, pos: Point2<i32>) -> Result<Self> {
+        let cluster =
+            conflict::clusters(tiles)
+                .into_iter()
+                .find(|cluster| cluster.contains(&pos))
+                .context("no intersection at position")?;
+
+        Ok(Self::from_cluster(tiles, cluster))
+    }
+
+    pub fn from_cluster(tiles: &TileMap<Tile>, cluster: Vec<Point2<i32>>) -> Self {
+        let approaches =
+            conflict::entries(tiles, &cluster)
+                .into_iter()
+                .map(|(pos, dir)| Approach::new(tiles, pos, dir.out_dir()))
+                .collect();
+
+        Self {
+            tiles:  cluster.clone(),
+            matrix: ConflictMatrix::new(tiles, cluster),
+            approaches,
+        }
+    }
+
+    pub fn all(tiles: &TileMap<Tile>) -> Vec<Self> {
+        conflict::clusters(tiles)
+            .into_iter()
+            .map(|cluster| Self::from_cluster(tiles, cluster))
+            .collect()
+    }
+
+    pub fn contains(&self, pos: Point2<i32>) -> bool {
+        self.tiles.contains(&pos)
+    }
+
+    pub fn distance(&self, pos: Point2<i32>) -> i32 {
+        self.tiles
+            .iter()
+            .map(|tile| (tile.x - pos.x).abs().max((tile.y - pos.y).abs()))
+            .min()
+            .unwrap_or(i32::MAX)
+    }
+
+    pub fn center(&self) -> Point2<f32> {
+        let sum =
+            self.tiles
+                .iter()
+                .fold(Vector2::zeros(), |sum, pos| {
+                    sum + Vector2::new(pos.x as f32, pos.y as f32)
+                });
+
+        Point2::from(sum / self.tiles.len() as f32)
+    }
+
+    pub fn dirs(&self) -> Vec<Cardinal> {
+        Cardinal::ALL
+            .into_iter()
+            .filter(|&dir| self.approaches.iter().any(|approach| approach.dir == dir))
+            .collect()
+    }
+
+    pub fn entries(&self, vehicles: &[Vehicle]) -> Vec<Cardinal> {
+        vehicles
+            .iter()
+            .filter(|vehicle| self.contains(vehicle.tile_pos))
+            .filter_map(|vehicle| vehicle.entry)
+            .collect()
+    }
+
+    pub fn approaching(&self, vehicles: &[Vehicle]) -> Vec<u32> {
+        vehicles
+            .iter()
+            .filter(|vehicle| !self.contains(vehicle.tile_pos))
+            .map(|vehicle| vehicle.id)
+            .collect()
+    }
+
+    pub fn occupied(&self, vehicles: &[Vehicle], dir: Cardinal) -> bool {
+        vehicles.iter().any(|vehicle| {
+            vehicle.entry == Some(dir) &&
+            self.contains(vehicle.tile_pos) &&
+            !vehicle.turning_left()
+        })
+    }
+
+    fn exits(&self, approach: &Approach, intent: Option<Cardinal>) -> Vec<Point2<i32>> {
+        let movements =
+            self.matrix.movements
+                .iter()
+                .filter(|movement| {
+                    movement.start() == approach.entry &&
+                    movement.entry == approach.dir
+                })
+                .collect::<Vec<_>>();
+
+        let intended =
+            movements
+                .iter()
+                .filter(|movement| intent.is_none_or(|intent| movement.exit == intent))
+                .collect::<Vec<_>>();
+
+        let movements =
+            if intended.is_empty() {
+                movements.iter().collect()
+            } else {
+                intended
+            };
+
+        movements
+            .into_iter()
+            .map(|movement| movement.end() + movement.exit.offset())
+            .collect()
+    }
+
+    pub fn spillback(&self, vehicles: &[Vehicle]) -> Vec<u32> {
+        self.approaches
+            .iter()
+            .filter_map(|approach| {
+                let vehicle = approach.waiting(vehicles)?;
+                let exits   = self.exits(approach, vehicle.intent);
+
+                let full =
+                    !exits.is_empty() &&
+                    exits.iter().all(|exit| {
+                        vehicles
+                            .iter()
+                            .any(|other| other.tile_pos == *exit && other.speed() < 1.0)
+                    });
+
+                full.then_some(vehicle.id)
+            })
+            .collect()
+    }
+
+    pub fn gap(&self, vehicles: &[Vehicle], dir: Cardinal) -> f32 {
+        self.approaches
+            .iter()
+            .filter(|approach| approach.dir == dir)
+            .map(|approach| approach.gap(vehicles))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn conflicting(&self, dir: Cardinal, other: Cardinal) -> bool {
+        dir != other && self.matrix.approaches_conflict(dir, other)
+    }
+
+    pub fn colliding<F>(&self, collider: &dyn Bounds, filter: F) -> bool
+    where
+        F: Fn(&Approach) -> bool,
+    {
+        self.approaches
+            .iter()
+            .filter(|approach| filter(approach))
+            .any(|approach| collider.colliding(&approach.stop_line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii_map::AsciiMap;
+
+    const CROSSING: &str = "  v^
+  v^
+<<++<<
+>>++>>
+  v^
+  v^
+";
+
+    const T_JUNCTION: &str = "<<++<<
+>>++>>
+  v^
+  v^
+";
+
+    fn intersection(map: &str, pos: (i32, i32)) -> Intersection {
+        let tiles = AsciiMap::parse(map).unwrap().tiles;
+
+        Intersection::new(&tiles, Point2::new(pos.0, pos.1)).unwrap()
+    }
+
+    fn approach(intersection: &Intersection, dir: Cardinal) -> &Approach {
+        intersection.approaches
+            .iter()
+            .find(|approach| approach.dir == dir)
+            .unwrap()
+    }
+
+    #[test]
+    fn groups_a_multi_tile_cluster() {
+        let intersection = intersection(CROSSING, (3, 3));
+
+        assert_eq!(intersection.tiles.len(), 4);
+        assert!(intersection.contains(Point2::new(2, 2)));
+        assert_eq!(intersection.center(), Point2::new(2.5, 2.5));
+        assert_eq!(intersection.dirs(), Cardinal::ALL);
+        assert_eq!(intersection.approaches.len(), 4);
+
+        assert!(intersection.approaches.iter().all(|approach| approach.tiles.len() == APPROACH_TILES));
+        assert_eq!(approach(&intersection, Cardinal::Down).tiles, [Point2::new(2, 1), Point2::new(2, 0)]);
+        assert_eq!(approach(&intersection, Cardinal::Left).entry, Point2::new(3, 2));
+    }
+
+    #[test]
+    fn finds_the_approaches_of_a_t_junction() {
+        let intersection = intersection(T_JUNCTION, (2, 0));
+
+        assert_eq!(intersection.tiles.len(), 4);
+        assert_eq!(intersection.dirs(), [Cardinal::Up, Cardinal::Right, Cardinal::Left]);
+        assert_eq!(approach(&intersection, Cardinal::Up).entry, Point2::new(3, 1));
+        assert!(intersection.conflicting(Cardinal::Up, Cardinal::Left));
+    }
+
+    #[test]
+    fn places_stop_lines_on_the_cluster_edge() {
+        let intersection = intersection(CROSSING, (2, 2));
+
+        let down = approach(&intersection, Cardinal::Down);
+
+        assert_eq!(down.midpoint(), Point2::new(2.0, 1.5) * TILE_SIZE_F);
+        assert_eq!(down.stop_line.0.y, down.stop_line.1.y);
+        assert_eq!(na::distance(&down.stop_line.0, &down.stop_line.1), TILE_SIZE_F);
+
+        let left = approach(&intersection, Cardinal::Left);
+
+        assert_eq!(left.midpoint(), Point2::new(3.5, 2.0) * TILE_SIZE_F);
+        assert_eq!(left.stop_line.0.x, left.stop_line.1.x);
+    }
+
+    #[test]
+    fn rejects_positions_outside_a_cluster() {
+        let tiles = AsciiMap::parse(CROSSING).unwrap().tiles;
+
+        assert!(Intersection::new(&tiles, Point2::new(0, 2)).is_err());
+        assert_eq!(Intersection::all(&tiles).len(), 1);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::conflict;
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::grid::Grid;
+use crate::random;
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+
+use parry2d::na::Point2;
+use rand::distributions::{Distribution, WeightedIndex};
+
+pub const LOOKAHEAD: usize = 64;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub const ALL: [Self; 2] = [Self::Left, Self::Right];
+
+    pub fn cardinal(&self, dir: Cardinal) -> Cardinal {
+        match self {
+            Self::Left  => dir.counter_rotate(),
+            Self::Right => dir.opposite().counter_rotate(),
+        }
+    }
+}
+
+pub fn neighbour(
+    tiles: &TileMap<Tile>,
+    pos:   Point2<i32>,
+    dir:   Cardinal,
+    side:  Side,
+) -> Option<Point2<i32>> {
+    let next = pos + side.cardinal(dir).offset();
+    let tile = tiles.get(&next)?;
+
+    (tile.dir == TileDirection::Constant(Direction::Straight(dir))).then_some(next)
+}
+
+pub fn lanes(tiles: &TileMap<Tile>, pos: Point2<i32>, dir: Cardinal) -> Vec<Point2<i32>> {
+    let mut lanes = vec![pos];
+
+    let mut cur = pos;
+    while let Some(next) = neighbour(tiles, cur, dir, Side::Left) {
+        lanes.insert(0, next);
+        cur = next;
+    }
+
+    let mut cur = pos;
+    while let Some(next) = neighbour(tiles, cur, dir, Side::Right) {
+        lanes.push(next);
+        cur = next;
+    }
+
+    lanes
+}
+
+pub fn downstream(
+    tiles: &TileMap<Tile>,
+    pos:   Point2<i32>,
+    dir:   Direction,
+) -> Option<(Point2<i32>, Direction)> {
+    let mut pos = pos;
+    let mut dir = dir;
+
+    for _ in 0..LOOKAHEAD {
+        let next = pos + dir.out_dir().offset();
+        let tile = tiles.get(&next)?;
+
+        match tile.dir {
+            TileDirection::Constant(next_dir) => {
+                pos = next;
+                dir = next_dir;
+            }
+
+            TileDirection::Intersection(_) => return Some((next, dir)),
+        }
+    }
+
+    None
+}
+
+pub fn exits(grid: &Grid, pos: Point2<i32>, dir: Direction) -> Vec<Cardinal> {
+    let Some(cluster) = grid.cluster(pos) else {
+        return Vec::new();
+    };
+
+    let mut exits = Vec::new();
+
+    for movement in conflict::movements(&grid.tiles, cluster, pos, dir) {
+        if !exits.contains(&movement.exit) {
+            exits.push(movement.exit);
+        }
+    }
+
+    exits
+}
+
+pub fn lane_exits(grid: &Grid, pos: Point2<i32>, dir: Direction) -> Vec<Cardinal> {
+    downstream(&grid.tiles, pos, dir)
+        .map(|(entry, dir)| exits(grid, entry, dir))
+        .unwrap_or_default()
+}
+
+pub fn reaches(grid: &Grid, pos: Point2<i32>, dir: Direction, intent: Cardinal) -> bool {
+    match grid.tiles.get(&pos).map(|tile| &tile.dir) {
+        Some(TileDirection::Intersection(_)) => exits(grid, pos, dir).contains(&intent),
+        _ => dir.out_dir() == intent,
+    }
+}
+
+pub fn pick_intent(grid: &Grid, pos: Point2<i32>, dir: Direction) -> Option<Cardinal> {
+    let lanes =
+        match dir {
+            Direction::Straight(heading) => lanes(&grid.tiles, pos, heading),
+            Direction::Turn(_, _)        => vec![pos],
+        };
+
+    let (_, entry_dir) = downstream(&grid.tiles, pos, dir)?;
+
+    let mut possible = Vec::new();
+
+    for lane in lanes {
+        for exit in lane_exits(grid, lane, dir) {
+            if !possible.contains(&exit) {
+                possible.push(exit);
+            }
+        }
+    }
+
+    let weights =
+        possible
+            .iter()
+            .map(|&exit| if exit == entry_dir.out_dir() { 3 } else { 1 })
+            .collect::<Vec<_>>();
+
+    let dist = WeightedIndex::new(&weights).ok()?;
+
+    Some(possible[random::with(|rng| dist.sample(rng))])
+}
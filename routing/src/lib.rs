@@ -1,10 +1,25 @@
+pub mod ascii_map;
 pub mod bounds;
+pub mod conflict;
 pub mod rect_bounds;
 pub mod segment_bounds;
-pub mod dir_bounds;
 pub mod direction;
+pub mod editor;
+pub mod grid;
+pub mod intersection;
+pub mod lane;
+pub mod mobil;
+pub mod osm;
+pub mod priority_sign;
+pub mod random;
+pub mod raster;
+pub mod road_grid;
 pub mod stop_sign;
 pub mod stoplight;
+pub mod sumo;
 pub mod tile;
 pub mod tile_map;
+pub mod tiled;
+pub mod traffic_control;
 pub mod vehicle;
+pub mod xml;
@@ -0,0 +1,258 @@
+use crate::direction::{Cardinal, Direction};
+use crate::grid::Grid;
+use crate::lane::{self, Side};
+use crate::tile_map::TILE_SIZE_F;
+use crate::vehicle::{Vehicle, ACCEL, MAX_SPEED};
+
+use parry2d::na::Point2;
+
+pub const POLITENESS: f32 = 0.3;
+pub const THRESHOLD:  f32 = 2.0;
+pub const BIAS:       f32 = 30.0;
+pub const SAFE_BRAKE: f32 = 20.0;
+
+pub const MIN_GAP: f32 = 2.0;
+pub const HEADWAY: f32 = 1.0;
+pub const COMFORT: f32 = 15.0;
+
+struct Neighbours<'a> {
+    leader:   Option<&'a Vehicle>,
+    follower: Option<&'a Vehicle>,
+}
+
+fn along(vehicle: &Vehicle, dir: Cardinal) -> f32 {
+    vehicle.center().coords.dot(&dir.vector())
+}
+
+fn gap(follower: &Vehicle, leader: &Vehicle, dir: Cardinal) -> f32 {
+    along(leader, dir) - along(follower, dir) - (follower.length() + leader.length()) / 2.0
+}
+
+fn neighbours<'a>(
+    vehicles: &[&'a Vehicle],
+    lane:     Point2<i32>,
+    dir:      Cardinal,
+    vehicle:  &Vehicle,
+) -> Neighbours<'a> {
+    let center = Point2::new(lane.x as f32, lane.y as f32) * TILE_SIZE_F;
+    let perp   = Side::Right.cardinal(dir).vector();
+
+    let own = along(vehicle, dir);
+
+    let in_lane =
+        vehicles
+            .iter()
+            .copied()
+            .filter(|other| {
+                other.id != vehicle.id &&
+                other.dir == Direction::Straight(dir) &&
+                (other.pos - center).dot(&perp).abs() < TILE_SIZE_F / 2.0
+            });
+
+    let mut neighbours = Neighbours {
+        leader:   None,
+        follower: None,
+    };
+
+    for other in in_lane {
+        let dist = along(other, dir) - own;
+
+        if dist >= 0.0 {
+            if neighbours.leader.is_none_or(|leader| along(leader, dir) - own > dist) {
+                neighbours.leader = Some(other);
+            }
+        } else if neighbours.follower.is_none_or(|follower| along(follower, dir) - own < dist) {
+            neighbours.follower = Some(other);
+        }
+    }
+
+    neighbours
+}
+
+pub fn accel(speed: f32, leader: Option<(f32, f32)>) -> f32 {
+    let max  = ACCEL * 60.0;
+    let free = 1.0 - (speed / MAX_SPEED).powi(4);
+
+    let interaction =
+        leader
+            .map(|(gap, leader_speed)| {
+                let desired =
+                    MIN_GAP +
+                    speed * HEADWAY +
+                    speed * (speed - leader_speed) / (2.0 * (max * COMFORT).sqrt());
+
+                (desired.max(0.0) / gap.max(0.1)).powi(2)
+            })
+            .unwrap_or(0.0);
+
+    max * (free - interaction)
+}
+
+fn accel_behind(follower: &Vehicle, leader: Option<&Vehicle>, dir: Cardinal) -> f32 {
+    accel(
+        follower.speed(),
+        leader.map(|leader| (gap(follower, leader, dir), leader.speed())),
+    )
+}
+
+pub fn decide(vehicle: &Vehicle, vehicles: &[&Vehicle], grid: &Grid) -> Option<Side> {
+    let Direction::Straight(dir) = vehicle.dir else {
+        return None;
+    };
+
+    let reaches = |pos: Point2<i32>| {
+        vehicle.intent.is_none_or(|intent| {
+            lane::lane_exits(grid, pos, vehicle.dir).contains(&intent)
+        })
+    };
+
+    let toward = |side: Side| {
+        let mut cur = vehicle.tile_pos;
+
+        while let Some(next) = lane::neighbour(&grid.tiles, cur, dir, side) {
+            if reaches(next) {
+                return true;
+            }
+
+            cur = next;
+        }
+
+        false
+    };
+
+    let targets =
+        Side::ALL
+            .into_iter()
+            .filter_map(|side| Some((side, lane::neighbour(&grid.tiles, vehicle.tile_pos, dir, side)?)))
+            .collect::<Vec<_>>();
+
+    if targets.is_empty() {
+        return None;
+    }
+
+    let cur_reaches = reaches(vehicle.tile_pos);
+    let cur = neighbours(vehicles, vehicle.tile_pos, dir, vehicle);
+
+    targets
+        .into_iter()
+        .filter_map(|(side, target)| {
+            let next = neighbours(vehicles, target, dir, vehicle);
+
+            let room =
+                next.leader.is_none_or(|leader| gap(vehicle, leader, dir) >= MIN_GAP) &&
+                next.follower.is_none_or(|follower| gap(follower, vehicle, dir) >= MIN_GAP);
+
+            if !room {
+                return None;
+            }
+
+            let own =
+                accel_behind(vehicle, next.leader, dir) -
+                accel_behind(vehicle, cur.leader, dir);
+
+            let new_follower =
+                match next.follower {
+                    Some(follower) => {
+                        let after = accel_behind(follower, Some(vehicle), dir);
+
+                        if after < -SAFE_BRAKE {
+                            return None;
+                        }
+
+                        after - accel_behind(follower, next.leader, dir)
+                    }
+
+                    None => 0.0,
+                };
+
+            let old_follower =
+                cur.follower
+                    .map(|follower| {
+                        accel_behind(follower, cur.leader, dir) -
+                        accel_behind(follower, Some(vehicle), dir)
+                    })
+                    .unwrap_or(0.0);
+
+            let bias =
+                if cur_reaches && !reaches(target) {
+                    -BIAS
+                } else if !cur_reaches && toward(side) {
+                    BIAS
+                } else {
+                    0.0
+                };
+
+            let incentive =
+                own + POLITENESS * (new_follower + old_follower) + bias;
+
+            (incentive > THRESHOLD).then_some((side, incentive))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(side, _)| side)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii_map::AsciiMap;
+
+    const TWO_LANES: &str = ">>>>>>>>>>>>
+>>>>>>>>>>>>
+";
+
+    const TURN_LANE: &str = "   ^
+>>>+>>
+>>>+>>
+@3,1 > >^
+@3,2 > >
+";
+
+    fn grid(map: &str) -> Grid {
+        Grid::new(AsciiMap::parse(map).unwrap().tiles, Vec::new())
+    }
+
+    fn vehicle(grid: &Grid, pos: (i32, i32), speed: f32) -> Vehicle {
+        Vehicle::new(Point2::new(pos.0 as f32, pos.1 as f32) * TILE_SIZE_F, 8.0, speed, grid)
+    }
+
+    #[test]
+    fn overtakes_into_a_safe_gap() {
+        let grid = grid(TWO_LANES);
+
+        let own    = vehicle(&grid, (5, 1), 10.0);
+        let leader = vehicle(&grid, (6, 1), 0.0);
+
+        assert_eq!(decide(&own, &[&leader], &grid), Some(Side::Left));
+    }
+
+    #[test]
+    fn stays_when_the_new_follower_would_brake_hard() {
+        let grid = grid(TWO_LANES);
+
+        let own      = vehicle(&grid, (5, 1), 10.0);
+        let leader   = vehicle(&grid, (6, 1), 0.0);
+        let follower = vehicle(&grid, (4, 0), MAX_SPEED);
+        let beside   = vehicle(&grid, (5, 0), 10.0);
+
+        assert_eq!(decide(&own, &[&leader, &follower], &grid), None);
+        assert_eq!(decide(&own, &[&leader, &beside], &grid), None);
+    }
+
+    #[test]
+    fn moves_into_the_turn_lane_before_an_intersection() {
+        let grid = grid(TURN_LANE);
+
+        let mut turning = vehicle(&grid, (1, 2), 0.0);
+        let mut settled = vehicle(&grid, (1, 1), 0.0);
+
+        turning.intent = Some(Cardinal::Up);
+        settled.intent = Some(Cardinal::Up);
+
+        assert_eq!(decide(&settled, &[], &grid), None);
+
+        turning.update(&[], &grid).unwrap();
+
+        assert_eq!(turning.tile_pos, Point2::new(1, 1));
+        assert!(lane::lane_exits(&grid, turning.tile_pos, turning.dir).contains(&Cardinal::Up));
+    }
+}
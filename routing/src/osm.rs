@@ -0,0 +1,565 @@
+use crate::grid::Grid;
+use crate::intersection::Intersection;
+use crate::priority_sign::PrioritySign;
+use crate::raster::{self, Lane, Road, METERS_PER_TILE, STAIR_STEP};
+use crate::stop_sign::StopSign;
+use crate::stoplight::Stoplight;
+use crate::traffic_control::TrafficControl;
+use crate::xml::attr;
+
+use parry2d::na::Point2;
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use anyhow::{bail, Context, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const SIGNAL_RADIUS: i32 = 3;
+
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+const DRIVABLE: [&str; 15] = [
+    "motorway",
+    "motorway_link",
+    "trunk",
+    "trunk_link",
+    "primary",
+    "primary_link",
+    "secondary",
+    "secondary_link",
+    "tertiary",
+    "tertiary_link",
+    "unclassified",
+    "residential",
+    "living_street",
+    "service",
+    "road",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Sign {
+    Stoplight,
+    Stop,
+    AllWayStop,
+}
+
+struct Signed {
+    pos:   Point2<i32>,
+    sign:  Sign,
+    roads: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Oneway {
+    No,
+    Forward,
+    Backward,
+}
+
+struct Node {
+    lat:  f64,
+    lon:  f64,
+    sign: Option<Sign>,
+}
+
+struct Way {
+    nodes:   Vec<i64>,
+    oneway:  Oneway,
+    highway: String,
+}
+
+impl Way {
+    fn rank(&self) -> usize {
+        DRIVABLE
+            .iter()
+            .position(|&highway| highway == self.highway)
+            .unwrap_or(DRIVABLE.len())
+    }
+}
+
+enum Element {
+    None,
+    Node(i64, Node, HashMap<String, String>),
+    Way(Vec<i64>, HashMap<String, String>),
+}
+
+pub struct OsmImporter {
+    pub meters_per_tile: f64,
+    pub stair_step:      i32,
+    pub signal_radius:   i32,
+    pub freq:            f32,
+}
+
+impl Default for OsmImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OsmImporter {
+    pub fn new() -> Self {
+        Self {
+            meters_per_tile: METERS_PER_TILE,
+            stair_step:      STAIR_STEP,
+            signal_radius:   SIGNAL_RADIUS,
+            freq:            10.0,
+        }
+    }
+
+    pub fn import<P: AsRef<Path>>(&self, path: P) -> Result<Grid> {
+        let xml = fs::read_to_string(path).context("failed to read OSM file")?;
+
+        self.parse(&xml)
+    }
+
+    pub fn parse(&self, xml: &str) -> Result<Grid> {
+        let (nodes, ways) = read(xml)?;
+        let ways = merge(ways);
+
+        let used =
+            ways.iter()
+                .flat_map(|way| way.nodes.iter())
+                .filter_map(|id| nodes.get(id))
+                .collect::<Vec<_>>();
+
+        if used.is_empty() {
+            bail!("no drivable ways in OSM file");
+        }
+
+        let origin = (
+            used.iter().map(|node| node.lat).sum::<f64>() / used.len() as f64,
+            used.iter().map(|node| node.lon).sum::<f64>() / used.len() as f64,
+        );
+
+        let project = |node: &Node| {
+            let x = (node.lon - origin.1).to_radians() * origin.0.to_radians().cos() * EARTH_RADIUS;
+            let y = -(node.lat - origin.0).to_radians() * EARTH_RADIUS;
+
+            Point2::new(
+                (x / self.meters_per_tile).round() as i32,
+                (y / self.meters_per_tile).round() as i32,
+            )
+        };
+
+        let roads =
+            ways.iter()
+                .map(|way| {
+                    let mut points =
+                        way.nodes
+                            .iter()
+                            .filter_map(|id| nodes.get(id))
+                            .map(project)
+                            .collect::<Vec<_>>();
+
+                    if way.oneway == Oneway::Backward {
+                        points.reverse();
+                    }
+
+                    Road::new(points, way.oneway == Oneway::No)
+                })
+                .collect::<Vec<_>>();
+
+        let (mut grid, lanes) = raster::rasterize(&roads, self.stair_step);
+
+        let signs =
+            nodes
+                .iter()
+                .filter_map(|(id, node)| {
+                    let sign = node.sign?;
+
+                    let on =
+                        ways.iter()
+                            .enumerate()
+                            .filter(|(_, way)| way.nodes.contains(id))
+                            .collect::<Vec<_>>();
+
+                    let minor = on.iter().map(|(_, way)| way.rank()).max().unwrap_or_default();
+
+                    Some(Signed {
+                        pos:   project(node),
+                        sign,
+                        roads: on.iter().filter(|(_, way)| way.rank() == minor).map(|&(idx, _)| idx).collect(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+        let mut controls = Vec::new();
+
+        for intersection in Intersection::all(&grid.tiles) {
+            controls.extend(self.control(intersection, &signs, &lanes));
+        }
+
+        grid.controls = controls;
+
+        Ok(grid)
+    }
+
+    fn control(&self, intersection: Intersection, signs: &[Signed], lanes: &[Lane]) -> Option<Box<dyn TrafficControl>> {
+        let nearby =
+            signs
+                .iter()
+                .filter(|signed| intersection.distance(signed.pos) <= self.signal_radius)
+                .collect::<Vec<_>>();
+
+        let has = |sign: Sign| nearby.iter().any(|signed| signed.sign == sign);
+
+        let control: Box<dyn TrafficControl> =
+            if has(Sign::Stoplight) {
+                let pos = intersection.tiles[0];
+
+                match Stoplight::new(intersection, self.freq) {
+                    Ok(stoplight) => Box::new(stoplight),
+
+                    Err(err) => {
+                        eprintln!("skipping traffic signal at {pos:?}: {err:#}");
+                        return None;
+                    }
+                }
+            } else if has(Sign::AllWayStop) {
+                Box::new(StopSign::new(intersection))
+            } else if has(Sign::Stop) {
+                let stopped =
+                    nearby
+                        .iter()
+                        .filter(|signed| signed.sign == Sign::Stop)
+                        .flat_map(|signed| signed.roads.iter().copied())
+                        .collect::<Vec<_>>();
+
+                let mut minor =
+                    intersection.approaches
+                        .iter()
+                        .filter(|approach| {
+                            let upstream = approach.tiles.first().copied().unwrap_or(approach.entry - approach.dir.offset());
+
+                            lanes
+                                .iter()
+                                .find(|lane| lane.cells.contains(&upstream))
+                                .is_some_and(|lane| stopped.contains(&lane.road))
+                        })
+                        .map(|approach| approach.dir)
+                        .collect::<Vec<_>>();
+
+                minor.dedup();
+
+                if minor.is_empty() {
+                    return None;
+                } else if minor.len() == intersection.dirs().len() {
+                    Box::new(StopSign::new(intersection))
+                } else {
+                    Box::new(PrioritySign::two_way_stop(intersection, minor))
+                }
+            } else {
+                return None;
+            };
+
+        Some(control)
+    }
+}
+
+fn read(xml: &str) -> Result<(HashMap<i64, Node>, Vec<Way>)> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut nodes   = HashMap::new();
+    let mut ways    = Vec::new();
+    let mut element = Element::None;
+
+    loop {
+        let (start, empty) =
+            match reader.read_event().context("malformed OSM XML")? {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+
+                Event::End(end) => {
+                    close(end.name().as_ref(), &mut element, &mut nodes, &mut ways);
+                    continue;
+                }
+
+                Event::Eof => break,
+                _          => continue,
+            };
+
+        match start.name().as_ref() {
+            b"node" => {
+                element = Element::Node(
+                    attr(&start, "id")?.parse()?,
+                    Node {
+                        lat:  attr(&start, "lat")?.parse()?,
+                        lon:  attr(&start, "lon")?.parse()?,
+                        sign: None,
+                    },
+                    HashMap::new(),
+                );
+            }
+
+            b"way" => {
+                element = Element::Way(Vec::new(), HashMap::new());
+            }
+
+            b"nd" => {
+                if let Element::Way(refs, _) = &mut element {
+                    refs.push(attr(&start, "ref")?.parse()?);
+                }
+            }
+
+            b"tag" => {
+                let key   = attr(&start, "k")?;
+                let value = attr(&start, "v")?;
+
+                match &mut element {
+                    Element::Node(_, _, tags) | Element::Way(_, tags) => {
+                        tags.insert(key, value);
+                    }
+
+                    Element::None => {}
+                }
+            }
+
+            _ => {}
+        }
+
+        if empty {
+            close(start.name().as_ref(), &mut element, &mut nodes, &mut ways);
+        }
+    }
+
+    Ok((nodes, ways))
+}
+
+fn close(name: &[u8], element: &mut Element, nodes: &mut HashMap<i64, Node>, ways: &mut Vec<Way>) {
+    if name != b"node" && name != b"way" {
+        return;
+    }
+
+    match std::mem::replace(element, Element::None) {
+        Element::Node(id, mut node, tags) => {
+            node.sign = sign(&tags);
+            nodes.insert(id, node);
+        }
+
+        Element::Way(refs, tags) => {
+            if let Some(oneway) = oneway(&tags) {
+                ways.push(Way { nodes: refs, oneway, highway: tags["highway"].clone() });
+            }
+        }
+
+        Element::None => {}
+    }
+}
+
+fn sign(tags: &HashMap<String, String>) -> Option<Sign> {
+    let all_way = tags.get("stop").is_some_and(|stop| stop == "all");
+
+    match tags.get("highway")?.as_str() {
+        "traffic_signals" => Some(Sign::Stoplight),
+        "stop" if all_way => Some(Sign::AllWayStop),
+        "stop"            => Some(Sign::Stop),
+        _                 => None,
+    }
+}
+
+fn oneway(tags: &HashMap<String, String>) -> Option<Oneway> {
+    let highway = tags.get("highway")?;
+
+    if !DRIVABLE.contains(&highway.as_str()) || tags.get("area").is_some_and(|area| area == "yes") {
+        return None;
+    }
+
+    let implied =
+        highway == "motorway" ||
+        tags.get("junction").is_some_and(|junction| junction == "roundabout" || junction == "circular");
+
+    match tags.get("oneway").map(String::as_str) {
+        Some("yes" | "true" | "1") => Some(Oneway::Forward),
+        Some("-1" | "reverse")     => Some(Oneway::Backward),
+        Some("no" | "false" | "0") => Some(Oneway::No),
+        _ if implied               => Some(Oneway::Forward),
+        _                          => Some(Oneway::No),
+    }
+}
+
+fn merge(ways: Vec<Way>) -> Vec<Way> {
+    let mut degree: HashMap<i64, usize> = HashMap::new();
+
+    for way in &ways {
+        for (idx, &id) in way.nodes.iter().enumerate() {
+            let end = idx == 0 || idx == way.nodes.len() - 1;
+            *degree.entry(id).or_default() += if end { 1 } else { 2 };
+        }
+    }
+
+    let mut ways = ways.into_iter().map(Some).collect::<Vec<_>>();
+
+    for (&node, _) in degree.iter().filter(|(_, &degree)| degree == 2) {
+        let ends =
+            ways.iter()
+                .enumerate()
+                .filter(|(_, way)| {
+                    way.as_ref().is_some_and(|way| {
+                        way.nodes.first() == Some(&node) || way.nodes.last() == Some(&node)
+                    })
+                })
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+
+        let [a, b] = ends[..] else {
+            continue;
+        };
+
+        let (Some(mut first), Some(mut second)) = (ways[a].take(), ways[b].take()) else {
+            continue;
+        };
+
+        if first.oneway == Oneway::No && second.oneway == Oneway::No {
+            if first.nodes.first() == Some(&node) {
+                first.nodes.reverse();
+            }
+
+            if second.nodes.last() == Some(&node) {
+                second.nodes.reverse();
+            }
+        } else if first.nodes.first() == Some(&node) && second.nodes.last() == Some(&node) {
+            std::mem::swap(&mut first, &mut second);
+        }
+
+        if first.oneway != second.oneway ||
+            first.highway != second.highway ||
+            first.nodes.last() != Some(&node) ||
+            second.nodes.first() != Some(&node)
+        {
+            ways[a] = Some(first);
+            ways[b] = Some(second);
+            continue;
+        }
+
+        first.nodes.extend(&second.nodes[1..]);
+        ways[a] = Some(first);
+    }
+
+    ways.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ascii_map::AsciiMap;
+    use crate::direction::{Cardinal, Direction, TileDirection};
+    use crate::priority_sign::Priority;
+    use crate::traffic_control::ControlState;
+
+    fn crossing(tags: &str, minor: &str) -> String {
+        format!(r#"<?xml version="1.0"?>
+<osm version="0.6">
+  <node id="1" lat="0.0" lon="-0.001"/>
+  <node id="2" lat="0.0" lon="0.0">
+    {tags}
+  </node>
+  <node id="3" lat="0.0" lon="0.001"/>
+  <node id="4" lat="0.001" lon="0.0"/>
+  <node id="5" lat="-0.001" lon="0.0"/>
+  <way id="10">
+    <nd ref="1"/><nd ref="2"/><nd ref="3"/>
+    <tag k="highway" v="residential"/>
+  </way>
+  <way id="11">
+    <nd ref="4"/><nd ref="2"/><nd ref="5"/>
+    <tag k="highway" v="{minor}"/>
+    <tag k="oneway" v="yes"/>
+  </way>
+  <way id="12">
+    <nd ref="1"/><nd ref="4"/>
+    <tag k="highway" v="footway"/>
+  </way>
+</osm>
+"#)
+    }
+
+    fn headings(grid: &Grid, x: i32) -> Vec<Cardinal> {
+        let mut headings =
+            grid.tiles.tiles
+                .iter()
+                .filter(|tile| tile.pos.x == x)
+                .filter_map(|tile| match tile.dir {
+                    TileDirection::Constant(Direction::Straight(dir)) => Some(dir),
+                    _                                                 => None,
+                })
+                .collect::<Vec<_>>();
+
+        headings.dedup();
+        headings
+    }
+
+    #[test]
+    fn rasterizes_a_signalled_crossing() {
+        let grid = OsmImporter::new().parse(&crossing(r#"<tag k="highway" v="traffic_signals"/>"#, "residential")).unwrap();
+
+        assert_eq!(grid.clusters.len(), 1);
+        assert_eq!(grid.entries.len(), 3);
+        assert_eq!(grid.exits.len(), 3);
+        assert_eq!(grid.controls.len(), 1);
+        assert!(matches!(grid.controls[0].state(), ControlState::Stoplight { .. }));
+
+        let column = grid.clusters[0].iter().map(|pos| pos.x).min().unwrap();
+
+        assert_eq!(headings(&grid, column), vec![Cardinal::Down]);
+    }
+
+    #[test]
+    fn stops_the_minor_road() {
+        let grid = OsmImporter::new().parse(&crossing(r#"<tag k="highway" v="stop"/>"#, "service")).unwrap();
+
+        assert_eq!(grid.controls.len(), 1);
+
+        let ControlState::PrioritySign { priority, lines } = grid.controls[0].state() else {
+            panic!("expected a priority sign");
+        };
+
+        assert_eq!(priority, Priority::Stop);
+        assert_eq!(lines.len(), 1);
+    }
+
+    #[test]
+    fn stops_the_road_carrying_the_sign() {
+        let xml =
+            crossing("", "residential")
+                .replace(r#"<nd ref="4"/><nd ref="2"/>"#, r#"<nd ref="4"/><nd ref="6"/><nd ref="2"/>"#)
+                .replace("</osm>", r#"<node id="6" lat="0.00015" lon="0.0"><tag k="highway" v="stop"/></node></osm>"#);
+
+        let grid = OsmImporter::new().parse(&xml).unwrap();
+
+        assert_eq!(grid.controls.len(), 1);
+        assert!(matches!(grid.controls[0].state(), ControlState::PrioritySign { lines, .. } if lines.len() == 1));
+    }
+
+    #[test]
+    fn places_all_way_stops() {
+        let all_way = r#"<tag k="highway" v="stop"/><tag k="stop" v="all"/>"#;
+
+        for (tags, minor) in [(all_way, "service"), (r#"<tag k="highway" v="stop"/>"#, "residential")] {
+            let grid = OsmImporter::new().parse(&crossing(tags, minor)).unwrap();
+
+            assert_eq!(grid.controls.len(), 1);
+            assert!(matches!(grid.controls[0].state(), ControlState::StopSign { .. }));
+        }
+    }
+
+    #[test]
+    fn skips_signals_without_phases() {
+        let tiles        = AsciiMap::parse(">").unwrap().tiles;
+        let intersection = Intersection::from_cluster(&tiles, vec![Point2::origin()]);
+
+        let control = OsmImporter::new().control(intersection, &[Signed { pos: Point2::origin(), sign: Sign::Stoplight, roads: Vec::new() }], &[]);
+
+        assert!(control.is_none());
+    }
+
+    #[test]
+    fn rejects_files_without_roads() {
+        let xml = r#"<osm><node id="1" lat="0" lon="0"/><node id="2" lat="0" lon="0.001"/>
+            <way id="3"><nd ref="1"/><nd ref="2"/><tag k="highway" v="footway"/></way></osm>"#;
+
+        assert!(OsmImporter::new().parse(xml).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::bounds::Bounds;
+use crate::direction::Cardinal;
+use crate::intersection::Intersection;
+use crate::traffic_control::{ControlState, TrafficControl};
+use crate::vehicle::Vehicle;
+
+use std::collections::HashMap;
+
+pub const CRITICAL_GAP: f32 = 4.0;
+pub const FOLLOW_UP:    f32 = 2.5;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Priority {
+    Yield,
+    Stop,
+}
+
+pub struct PrioritySign {
+    pub intersection: Intersection,
+
+    pub priority: Priority,
+    pub minor:    Vec<Cardinal>,
+
+    pub critical_gap: f32,
+    pub follow_up:    f32,
+
+    oncoming:  Vec<Cardinal>,
+    inside:    Vec<Cardinal>,
+    spillback: Vec<u32>,
+    stopped:   Vec<u32>,
+    released:  Vec<u32>,
+    last:      HashMap<Cardinal, u32>,
+}
+
+impl PrioritySign {
+    pub fn new(intersection: Intersection, priority: Priority, minor: Vec<Cardinal>) -> Self {
+        Self {
+            intersection,
+            priority,
+            minor,
+            critical_gap: CRITICAL_GAP,
+            follow_up:    FOLLOW_UP,
+            oncoming:  Vec::new(),
+            inside:    Vec::new(),
+            spillback: Vec::new(),
+            stopped:   Vec::new(),
+            released:  Vec::new(),
+            last:      HashMap::new(),
+        }
+    }
+
+    pub fn two_way_stop(intersection: Intersection, minor: Vec<Cardinal>) -> Self {
+        Self::new(intersection, Priority::Stop, minor)
+    }
+
+    pub fn yield_sign(intersection: Intersection, minor: Vec<Cardinal>) -> Self {
+        Self::new(intersection, Priority::Yield, minor)
+    }
+
+    pub fn is_minor(&self, dir: Cardinal) -> bool {
+        self.minor.contains(&dir)
+    }
+
+    pub fn gap(&self, vehicles: &[Vehicle]) -> f32 {
+        self.intersection.approaches
+            .iter()
+            .filter(|approach| !self.is_minor(approach.dir))
+            .map(|approach| approach.gap(vehicles))
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+impl TrafficControl for PrioritySign {
+    fn update(&mut self, time: u32, vehicles: &[Vehicle]) {
+        let approaching = self.intersection.approaching(vehicles);
+
+        self.stopped.retain(|id| approaching.contains(id));
+        self.released.retain(|id| approaching.contains(id));
+
+        let entries = self.intersection.entries(vehicles);
+
+        let major_inside =
+            entries
+                .iter()
+                .any(|&dir| !self.is_minor(dir));
+
+        let gap = self.gap(vehicles);
+
+        let mut claimed =
+            vehicles
+                .iter()
+                .filter(|vehicle| self.released.contains(&vehicle.id))
+                .map(|vehicle| vehicle.dir.out_dir())
+                .chain(entries.iter().copied())
+                .collect::<Vec<_>>();
+
+        self.inside    = entries.clone();
+        self.spillback = self.intersection.spillback(vehicles);
+
+        self.oncoming =
+            self.intersection
+                .dirs()
+                .into_iter()
+                .filter(|&dir| {
+                    !self.is_minor(dir) && (
+                        self.intersection.occupied(vehicles, dir) ||
+                        self.intersection.gap(vehicles, dir) < self.critical_gap
+                    )
+                })
+                .collect();
+
+        for approach in &self.intersection.approaches {
+            let dir = approach.dir;
+
+            if !self.minor.contains(&dir) {
+                continue;
+            }
+
+            let Some(vehicle) = approach.waiting(vehicles) else {
+                continue;
+            };
+
+            if self.released.contains(&vehicle.id) {
+                continue;
+            }
+
+            if vehicle.speed() == 0.0 && !self.stopped.contains(&vehicle.id) {
+                self.stopped.push(vehicle.id);
+            }
+
+            let ready =
+                self.priority == Priority::Yield ||
+                self.stopped.contains(&vehicle.id);
+
+            let since =
+                self.last
+                    .get(&dir)
+                    .map(|&last| time.saturating_sub(last) as f32 / 60.0)
+                    .unwrap_or(f32::INFINITY);
+
+            let required =
+                if since < self.critical_gap {
+                    self.follow_up
+                } else {
+                    self.critical_gap
+                };
+
+            let conflicting =
+                claimed
+                    .iter()
+                    .any(|&other| self.intersection.conflicting(dir, other));
+
+            let accepted =
+                ready &&
+                !self.spillback.contains(&vehicle.id) &&
+                !major_inside &&
+                !conflicting &&
+                since >= self.follow_up &&
+                gap >= required;
+
+            if accepted {
+                self.released.push(vehicle.id);
+                self.last.insert(dir, time);
+
+                claimed.push(dir);
+            }
+        }
+    }
+
+    fn blocks(&self, vehicle: &Vehicle, collider: &dyn Bounds) -> bool {
+        let dir = vehicle.entry.unwrap_or(vehicle.dir.out_dir());
+
+        let yielding =
+            !self.is_minor(dir) &&
+            vehicle.turning_left() &&
+            self.oncoming.contains(&dir.opposite());
+
+        if self.intersection.contains(vehicle.tile_pos) {
+            yielding
+        } else if !self.is_minor(dir) {
+            let opposing =
+                vehicle.turning_left() &&
+                self.inside.contains(&dir.opposite());
+
+            let crossing =
+                self.inside
+                    .iter()
+                    .any(|&other| self.is_minor(other) && self.intersection.conflicting(dir, other));
+
+            let waiting =
+                yielding || opposing || crossing || self.spillback.contains(&vehicle.id);
+
+            waiting && self.intersection.colliding(collider, |approach| approach.dir == dir)
+        } else if self.released.contains(&vehicle.id) {
+            false
+        } else {
+            self.intersection.colliding(collider, |approach| approach.dir == dir)
+        }
+    }
+
+    fn state(&self) -> ControlState {
+        let lines =
+            self.intersection.approaches
+                .iter()
+                .filter(|approach| self.is_minor(approach.dir))
+                .map(|approach| approach.stop_line.clone())
+                .collect();
+
+        ControlState::PrioritySign {
+            priority: self.priority,
+            lines,
+        }
+    }
+
+    fn intersection(&self) -> &Intersection {
+        &self.intersection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii_map::AsciiMap;
+    use crate::grid::Grid;
+    use crate::tile_map::TILE_SIZE_F;
+
+    use parry2d::na::Point2;
+
+    const STRAIGHT: &str = "  v^
+  v^
+<<++<<
+>>++>>
+  v^
+  v^
+@2,2 v v
+@2,2 < <
+@3,2 < <
+@3,2 ^ ^
+@2,3 v v
+@2,3 > >
+@3,3 > >
+@3,3 ^ ^
+";
+
+    fn setup() -> (Grid, PrioritySign) {
+        let map          = AsciiMap::parse(STRAIGHT).unwrap();
+        let grid         = Grid::new(map.tiles, Vec::new());
+        let intersection = Intersection::new(&grid.tiles, Point2::new(2, 2)).unwrap();
+
+        (grid, PrioritySign::two_way_stop(intersection, vec![Cardinal::Left, Cardinal::Right]))
+    }
+
+    fn vehicle(grid: &Grid, pos: (i32, i32), speed: f32) -> Vehicle {
+        Vehicle::new(Point2::new(pos.0 as f32, pos.1 as f32) * TILE_SIZE_F, 8.0, speed, grid)
+    }
+
+    fn blocked(sign: &PrioritySign, vehicle: &Vehicle) -> bool {
+        let approach =
+            sign.intersection.approaches
+                .iter()
+                .find(|approach| approach.dir == vehicle.dir.out_dir())
+                .unwrap();
+
+        sign.blocks(vehicle, &approach.stop_line)
+    }
+
+    #[test]
+    fn accepts_a_clear_gap() {
+        let (grid, mut sign) = setup();
+
+        let minor = vehicle(&grid, (1, 3), 0.0);
+
+        assert!(blocked(&sign, &minor));
+
+        sign.update(1, &[minor]);
+
+        assert_eq!(sign.gap(&[minor]), f32::INFINITY);
+        assert!(!blocked(&sign, &minor));
+    }
+
+    #[test]
+    fn rejects_a_short_gap() {
+        let (grid, mut sign) = setup();
+
+        let minor = vehicle(&grid, (1, 3), 0.0);
+        let major = vehicle(&grid, (2, 0), 8.0);
+
+        sign.update(1, &[minor, major]);
+
+        assert!(sign.gap(&[minor, major]) < sign.critical_gap);
+        assert!(blocked(&sign, &minor));
+        assert!(!blocked(&sign, &major));
+    }
+
+    #[test]
+    fn holds_major_traffic_behind_a_crossing_minor_vehicle() {
+        let (grid, mut sign) = setup();
+
+        let mut minor = vehicle(&grid, (1, 3), 0.0);
+        let major     = vehicle(&grid, (2, 1), 0.0);
+
+        minor.tile_pos = Point2::new(2, 3);
+        minor.entry    = Some(Cardinal::Right);
+
+        sign.update(1, &[major]);
+
+        assert!(!blocked(&sign, &major));
+
+        sign.update(2, &[minor, major]);
+
+        assert!(blocked(&sign, &major));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::conflict;
+use crate::direction::{Cardinal, Direction};
+use crate::grid::Grid;
+use crate::road_grid::RoadGrid;
+use crate::tile_map::TILE_SIZE_F;
+
+use parry2d::na::{Point2, Vector2};
+
+use std::collections::HashSet;
+
+pub const METERS_PER_TILE: f64 = 8.0;
+pub const STAIR_STEP:      i32 = 3;
+
+pub struct Road {
+    pub points:  Vec<Point2<i32>>,
+    pub two_way: bool,
+}
+
+impl Road {
+    pub fn new(points: Vec<Point2<i32>>, two_way: bool) -> Self {
+        Self { points, two_way }
+    }
+}
+
+pub struct Lane {
+    pub road:    usize,
+    pub forward: bool,
+    pub cells:   Vec<Point2<i32>>,
+}
+
+pub fn rasterize(roads: &[Road], stair_step: i32) -> (Grid, Vec<Lane>) {
+    let mut lanes = Vec::new();
+
+    for (idx, road) in roads.iter().enumerate() {
+        if road.points.len() < 2 {
+            continue;
+        }
+
+        let forward = polyline(&road.points, stair_step);
+
+        if road.two_way {
+            let backward = offset(&forward).into_iter().rev().collect::<Vec<_>>();
+
+            lanes.push(Lane { road: idx, forward: false, cells: cells(&backward) });
+        }
+
+        lanes.push(Lane { road: idx, forward: true, cells: cells(&forward) });
+    }
+
+    lanes.retain(|lane| lane.cells.len() >= 2);
+
+    let occupied =
+        lanes
+            .iter()
+            .flat_map(|lane| lane.cells.iter().copied())
+            .collect::<HashSet<_>>();
+
+    for lane in &mut lanes {
+        let cells = &mut lane.cells;
+
+        let len   = cells.len();
+        let last  = cells[len - 1] + heading(cells[len - 2], cells[len - 1]).offset();
+        let first = cells[0] - heading(cells[0], cells[1]).offset();
+
+        if occupied.contains(&last) && !cells.contains(&last) {
+            cells.push(last);
+        }
+
+        if occupied.contains(&first) && !cells.contains(&first) {
+            cells.insert(0, first);
+        }
+    }
+
+    let mut grid = RoadGrid::new();
+
+    for lane in &lanes {
+        let headings =
+            lane.cells
+                .windows(2)
+                .map(|pair| heading(pair[0], pair[1]))
+                .collect::<Vec<_>>();
+
+        for (idx, &pos) in lane.cells.iter().enumerate() {
+            let in_dir  = headings[idx.saturating_sub(1)];
+            let out_dir = headings[idx.min(headings.len() - 1)];
+
+            grid.add(pos, Direction::Straight(in_dir).towards(out_dir));
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut exits   = Vec::new();
+
+    for lane in &lanes {
+        let cells = &lane.cells;
+
+        let first = cells[0];
+        let last  = cells[cells.len() - 1];
+
+        let upstream = grid.get(&(first - heading(cells[0], cells[1]).offset()));
+
+        if upstream.is_none() && grid.get(&first).is_some_and(|dirs| dirs.len() == 1) {
+            entries.push(Point2::new(first.x as f32, first.y as f32) * TILE_SIZE_F);
+        }
+
+        if grid.get(&(last + heading(cells[cells.len() - 2], last).offset())).is_none() {
+            exits.push(last);
+        }
+    }
+
+    let tiles = grid.finish();
+
+    let grid = Grid {
+        clusters: conflict::clusters(&tiles),
+        tiles,
+        controls: Vec::new(),
+        entries,
+        exits,
+    };
+
+    (grid, lanes)
+}
+
+pub fn heading(from: Point2<i32>, to: Point2<i32>) -> Cardinal {
+    if to.x > from.x {
+        Cardinal::Right
+    } else if to.x < from.x {
+        Cardinal::Left
+    } else if to.y > from.y {
+        Cardinal::Down
+    } else {
+        Cardinal::Up
+    }
+}
+
+pub fn line(from: Point2<i32>, to: Point2<i32>) -> Vec<(Point2<i32>, Point2<i32>)> {
+    let delta = to - from;
+    let (dx, dy) = (delta.x.abs(), delta.y.abs());
+
+    let mut steps = Vec::with_capacity((dx + dy) as usize);
+    let mut cur   = from;
+    let (mut ix, mut iy) = (0, 0);
+
+    while ix < dx || iy < dy {
+        let next =
+            if (1 + 2 * ix) * dy < (1 + 2 * iy) * dx {
+                ix += 1;
+                cur + Vector2::new(delta.x.signum(), 0)
+            } else {
+                iy += 1;
+                cur + Vector2::new(0, delta.y.signum())
+            };
+
+        steps.push((cur, next));
+        cur = next;
+    }
+
+    steps
+}
+
+fn polyline(points: &[Point2<i32>], stair_step: i32) -> Vec<Point2<i32>> {
+    let mut vertices = vec![points[0]];
+
+    for pair in points.windows(2) {
+        let delta = pair[1] - pair[0];
+        let steps = (delta.x.abs().min(delta.y.abs()) / stair_step).max(1);
+
+        let mut prev = pair[0];
+
+        for step in 1..=steps {
+            let next = pair[0] + (delta * step) / steps;
+
+            let corner =
+                if (next.x - prev.x).abs() >= (next.y - prev.y).abs() {
+                    Point2::new(next.x, prev.y)
+                } else {
+                    Point2::new(prev.x, next.y)
+                };
+
+            vertices.push(corner);
+            vertices.push(next);
+
+            prev = next;
+        }
+    }
+
+    simplify(vertices)
+}
+
+fn simplify(mut vertices: Vec<Point2<i32>>) -> Vec<Point2<i32>> {
+    vertices.dedup();
+
+    let mut idx = 1;
+
+    while idx + 1 < vertices.len() {
+        let (a, b, c) = (vertices[idx - 1], vertices[idx], vertices[idx + 1]);
+
+        if (a.x == b.x && b.x == c.x) || (a.y == b.y && b.y == c.y) {
+            vertices.remove(idx);
+            vertices.dedup();
+
+            idx = idx.saturating_sub(1).max(1);
+        } else {
+            idx += 1;
+        }
+    }
+
+    vertices
+}
+
+fn offset(vertices: &[Point2<i32>]) -> Vec<Point2<i32>> {
+    let normals =
+        vertices
+            .windows(2)
+            .map(|pair| heading(pair[0], pair[1]).counter_rotate().offset())
+            .collect::<Vec<Vector2<i32>>>();
+
+    let shifted =
+        vertices
+            .iter()
+            .enumerate()
+            .map(|(idx, vertex)| {
+                let before = idx.checked_sub(1).map(|idx| normals[idx]);
+                let after  = normals.get(idx).copied();
+
+                match (before, after) {
+                    (Some(before), Some(after)) => vertex + before + after,
+                    (Some(normal), None)        => vertex + normal,
+                    (None, Some(normal))        => vertex + normal,
+                    (None, None)                => *vertex,
+                }
+            })
+            .collect();
+
+    simplify(shifted)
+}
+
+fn cells(vertices: &[Point2<i32>]) -> Vec<Point2<i32>> {
+    let mut cells = vec![vertices[0]];
+
+    for pair in vertices.windows(2) {
+        let step = heading(pair[0], pair[1]).offset();
+        let mut cur = pair[0];
+
+        while cur != pair[1] {
+            cur += step;
+            cells.push(cur);
+        }
+    }
+
+    cells
+}
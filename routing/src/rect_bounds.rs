@@ -2,10 +2,9 @@ use crate::bounds::Bounds;
 use crate::direction::Direction;
 use crate::vehicle::BRAKE;
 
-use parry2d::utils;
-use parry2d::shape::Shape;
+use parry2d::shape::{Cuboid, Shape};
 use parry2d::math::Isometry;
-use parry2d::na::{Point2, Vector2};
+use parry2d::na::{self, Point2, Vector2};
 
 use std::iter;
 
@@ -38,7 +37,7 @@ impl RectBounds {
 
         let projected: f32 =
             if matches!(dir, Direction::Turn(_, _)) {
-                length
+                0.0
             } else {
                 iter::successors(
                     Some(speed),
@@ -54,12 +53,24 @@ impl RectBounds {
 
         Self(front - side, front + side, pos - side, pos + side)
     }
+
+    pub fn corners(&self) -> [Point2<f32>; 4] {
+        [self.0, self.1, self.3, self.2]
+    }
 }
 
 impl Bounds for RectBounds {
     fn as_parry(&self) -> (Isometry<f32>, Box<dyn Shape>) {
-        let (iso, cuboid) =
-            utils::obb(&[self.0, self.1, self.2, self.3]);
+        let center = na::center(
+            &na::center(&self.0, &self.1),
+            &na::center(&self.2, &self.3),
+        );
+
+        let along  = self.0 - self.2;
+        let across = self.1 - self.0;
+
+        let iso    = Isometry::new(center.coords, along.y.atan2(along.x));
+        let cuboid = Cuboid::new(Vector2::new(along.norm() / 2.0, across.norm() / 2.0));
 
         (iso, Box::new(cuboid))
     }
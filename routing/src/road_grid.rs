@@ -0,0 +1,156 @@
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::tile::Tile;
+use crate::tile_map::TileMap;
+
+use parry2d::na::Point2;
+use anyhow::{bail, Result};
+
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct RoadGrid {
+    cells: HashMap<Point2<i32>, Vec<Direction>>,
+}
+
+impl RoadGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, pos: Point2<i32>, dir: Direction) {
+        let dirs = self.cells.entry(pos).or_default();
+
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    pub fn line(&mut self, start: Point2<i32>, dir: Cardinal, len: usize) {
+        let mut pos = start;
+
+        for _ in 0..len {
+            self.add(pos, Direction::Straight(dir));
+            pos += dir.offset();
+        }
+    }
+
+    pub fn get(&self, pos: &Point2<i32>) -> Option<&Vec<Direction>> {
+        self.cells.get(pos)
+    }
+
+    fn accepts(&self, pos: Point2<i32>, dir: Cardinal) -> bool {
+        self.cells
+            .get(&pos)
+            .is_some_and(|dirs| dirs.iter().any(|cell_dir| cell_dir.in_dir() == dir))
+    }
+
+    pub fn table(&self, pos: Point2<i32>) -> HashMap<Direction, Vec<Cardinal>> {
+        let mut table = HashMap::new();
+
+        for dir in self.cells.get(&pos).into_iter().flatten() {
+            let heading = dir.in_dir();
+
+            let outs =
+                [heading, heading.opposite().counter_rotate(), heading.counter_rotate()]
+                    .into_iter()
+                    .filter(|&out| {
+                        let next = pos + out.offset();
+
+                        self.accepts(next, out) && (out == heading || !self.accepts(next, heading))
+                    })
+                    .collect::<Vec<_>>();
+
+            table.insert(Direction::Straight(heading), outs);
+        }
+
+        table
+    }
+
+    pub fn tile(&self, pos: Point2<i32>) -> Option<Tile> {
+        let dirs = self.cells.get(&pos)?;
+
+        let tile_dir =
+            match dirs.as_slice() {
+                [dir] => TileDirection::Constant(*dir),
+                _     => TileDirection::Intersection(self.table(pos)),
+            };
+
+        Some(Tile::new(pos, tile_dir))
+    }
+
+    pub fn positions(&self) -> Vec<Point2<i32>> {
+        let mut positions = self.cells.keys().copied().collect::<Vec<_>>();
+        positions.sort_by_key(|pos| (pos.y, pos.x));
+
+        positions
+    }
+
+    pub fn finish(self) -> TileMap<Tile> {
+        TileMap::new(
+            self.positions()
+                .into_iter()
+                .filter_map(|pos| self.tile(pos))
+                .collect()
+        )
+    }
+}
+
+pub fn resolve(cells: &HashMap<Point2<i32>, Option<Direction>>) -> Result<TileMap<Tile>> {
+    let mut grid = RoadGrid::new();
+
+    for (&pos, &cell) in cells {
+        match cell {
+            Some(dir) => grid.add(pos, dir),
+            None => {
+                let headings = crossings(cells, pos);
+
+                if headings.is_empty() {
+                    bail!("intersection at {pos:?} is not connected to any road");
+                }
+
+                for heading in headings {
+                    grid.add(pos, Direction::Straight(heading));
+                }
+            }
+        }
+    }
+
+    let tiles =
+        grid.positions()
+            .into_iter()
+            .map(|pos| {
+                let dir =
+                    match cells[&pos] {
+                        Some(dir) => TileDirection::Constant(dir),
+                        None      => TileDirection::Intersection(grid.table(pos)),
+                    };
+
+                Tile::new(pos, dir)
+            })
+            .collect();
+
+    Ok(TileMap::new(tiles))
+}
+
+pub fn crossings(cells: &HashMap<Point2<i32>, Option<Direction>>, pos: Point2<i32>) -> Vec<Cardinal> {
+    Cardinal::ALL
+        .into_iter()
+        .filter(|&heading| crosses(cells, pos, heading))
+        .collect()
+}
+
+fn crosses(cells: &HashMap<Point2<i32>, Option<Direction>>, pos: Point2<i32>, heading: Cardinal) -> bool {
+    let walk = |step: Cardinal, connects: fn(&Direction) -> Cardinal| {
+        let mut cur = pos + step.offset();
+
+        loop {
+            match cells.get(&cur) {
+                Some(Some(dir)) => return connects(dir) == heading,
+                Some(None)      => cur += step.offset(),
+                None            => return false,
+            }
+        }
+    };
+
+    walk(heading.opposite(), Direction::out_dir) || walk(heading, Direction::in_dir)
+}
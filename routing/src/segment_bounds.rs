@@ -4,7 +4,7 @@ use parry2d::math::Isometry;
 use parry2d::shape::{Segment, Shape};
 use parry2d::na::Point2;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SegmentBounds(
     pub Point2<f32>,
     pub Point2<f32>,
@@ -1,115 +1,248 @@
 use crate::bounds::Bounds;
 use crate::vehicle::Vehicle;
 use crate::direction::Cardinal;
-use crate::dir_bounds::DirBounds;
-
-use parry2d::na::{Point2, Vector2};
+use crate::intersection::Intersection;
+use crate::traffic_control::{ControlState, TrafficControl};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Arrival {
+    pub id:   u32,
+    pub dir:  Cardinal,
+    pub time: u32,
+}
 
 pub struct StopSign {
-    pub pos: Point2<f32>,
-    pub dir: Cardinal,
-    moved_inside: bool,
+    pub intersection: Intersection,
+    arrivals: Vec<Arrival>,
+    released: Vec<Arrival>,
 }
 
 impl StopSign {
-    pub fn new(pos: Point2<f32>) -> Self {
+    pub fn new(intersection: Intersection) -> Self {
         Self {
-            pos,
-            dir: Cardinal::Up,
-            moved_inside: false,
+            intersection,
+            arrivals: Vec::new(),
+            released: Vec::new(),
         }
     }
 
-    pub fn colliding(&self, pos: Point2<i32>, collider: &dyn Bounds) -> bool {
-        let bounds = DirBounds::new(&self.pos);
+    pub fn arrivals(&self) -> &[Arrival] {
+        &self.arrivals
+    }
 
-        if self.pos_inside(pos) {
-            false
-        } else if self.moved_inside {
-            bounds.colliding(collider)
-        } else {
-            let side_bounds = match self.dir {
-                Cardinal::Up    => (bounds.up,   bounds.left, bounds.right),
-                Cardinal::Down  => (bounds.down, bounds.left, bounds.right),
-                Cardinal::Left  => (bounds.up,   bounds.down, bounds.left),
-                Cardinal::Right => (bounds.up,   bounds.down, bounds.right),
-            };
+    fn queue(&self) -> Vec<Arrival> {
+        let mut pending = self.arrivals.clone();
+        pending.sort_by_key(|arrival| arrival.time);
+
+        let mut queue = Vec::with_capacity(pending.len());
 
-            collider.colliding(&side_bounds.0) ||
-            collider.colliding(&side_bounds.1) ||
-            collider.colliding(&side_bounds.2)
+        while !pending.is_empty() {
+            let time = pending[0].time;
+
+            let next =
+                pending
+                    .iter()
+                    .position(|arrival| {
+                        let right = arrival.dir.counter_rotate();
+
+                        !pending.iter().any(|other| {
+                            other.time == time && other.dir == right
+                        })
+                    })
+                    .filter(|&idx| pending[idx].time == time)
+                    .unwrap_or(0);
+
+            queue.push(pending.remove(next));
         }
+
+        queue
     }
+}
+
+impl TrafficControl for StopSign {
+    fn update(&mut self, time: u32, vehicles: &[Vehicle]) {
+        let approaching = self.intersection.approaching(vehicles);
 
-    pub fn update(&mut self, vehicles: &[Vehicle]) {
-        let any_inside = self.any_inside(vehicles);
+        self.arrivals.retain(|arrival| approaching.contains(&arrival.id));
+        self.released.retain(|arrival| approaching.contains(&arrival.id));
 
-        if any_inside {
-            self.moved_inside = true;
+        for approach in &self.intersection.approaches {
+            let Some(vehicle) = approach.waiting(vehicles) else {
+                continue;
+            };
+
+            let known =
+                self.arrivals.iter().chain(&self.released).any(|arrival| {
+                    arrival.id == vehicle.id
+                });
+
+            if !known && vehicle.speed() == 0.0 {
+                self.arrivals.push(Arrival {
+                    id:  vehicle.id,
+                    dir: approach.dir,
+                    time,
+                });
+            }
         }
 
-        let mut dir = self.dir;
+        let spillback = self.intersection.spillback(vehicles);
 
-        for _ in 0..3 {
-            dir = dir.rotate();
+        let mut active = self.intersection.entries(vehicles);
+        active.extend(self.released.iter().map(|arrival| arrival.dir));
 
-            let should_rotate =
-                !any_inside &&
-                (self.moved_inside || !self.vehicle_outside(vehicles, self.dir)) &&
-                self.vehicle_outside(vehicles, dir);
+        for arrival in self.queue() {
+            let conflicting =
+                active
+                    .iter()
+                    .any(|&dir| self.intersection.conflicting(dir, arrival.dir));
 
-            if should_rotate {
-                self.dir = dir;
-                self.moved_inside = false;
-                break;
+            if !conflicting && !spillback.contains(&arrival.id) {
+                self.arrivals.retain(|other| other.id != arrival.id);
+                self.released.push(arrival);
             }
+
+            active.push(arrival.dir);
         }
     }
 
-    pub fn any_inside(&self, vehicles: &[Vehicle]) -> bool {
-        let f_pos = self.pos + Vector2::new(-0.5, -0.5);
-        let pos   = Point2::new(f_pos.x as i32, f_pos.y as i32);
-
-        [
-            Vector2::new(0, 0),
-            Vector2::new(1, 0),
-            Vector2::new(0, 1),
-            Vector2::new(1, 1),
-        ].iter().any(|offset| {
-            vehicles.iter().any(|vehicle| {
-                vehicle.tile_pos == pos + offset
-            })
-        })
+    fn blocks(&self, vehicle: &Vehicle, collider: &dyn Bounds) -> bool {
+        let released =
+            self.released
+                .iter()
+                .any(|arrival| arrival.id == vehicle.id);
+
+        if self.intersection.contains(vehicle.tile_pos) || released {
+            false
+        } else {
+            self.intersection.colliding(collider, |_| true)
+        }
     }
 
-    pub fn pos_inside(&self, pos: Point2<i32>) -> bool {
-        let f_pos = self.pos + Vector2::new(-0.5, -0.5);
-        let stoplight_pos = Point2::new(f_pos.x as i32, f_pos.y as i32);
-
-        [
-            Vector2::new(0, 0),
-            Vector2::new(1, 0),
-            Vector2::new(0, 1),
-            Vector2::new(1, 1),
-        ].iter().any(|offset| {
-            pos == stoplight_pos + offset
-        })
+    fn state(&self) -> ControlState {
+        let lines =
+            self.intersection.approaches
+                .iter()
+                .map(|approach| approach.stop_line.clone())
+                .collect();
+
+        ControlState::StopSign {
+            center: self.intersection.center(),
+            lines,
+        }
     }
 
-    pub fn vehicle_outside(&self, vehicles: &[Vehicle], dir: Cardinal) -> bool {
-        let offsets =
-            match dir {
-                Cardinal::Up    => [Vector2::new( 0.5,  1.5), Vector2::new( 0.5,  2.5)],
-                Cardinal::Down  => [Vector2::new(-0.5, -1.5), Vector2::new(-0.5, -2.5)],
-                Cardinal::Left  => [Vector2::new( 1.5, -0.5), Vector2::new( 2.5, -0.5)],
-                Cardinal::Right => [Vector2::new(-1.5,  0.5), Vector2::new(-2.5,  0.5)],
-            };
+    fn intersection(&self) -> &Intersection {
+        &self.intersection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii_map::AsciiMap;
+    use crate::grid::Grid;
+    use crate::tile_map::TILE_SIZE_F;
+
+    use parry2d::na::Point2;
+
+    const STRAIGHT: &str = "  v^
+  v^
+<<++<<
+>>++>>
+  v^
+  v^
+@2,2 v v
+@2,2 < <
+@3,2 < <
+@3,2 ^ ^
+@2,3 v v
+@2,3 > >
+@3,3 > >
+@3,3 ^ ^
+";
+
+    fn setup() -> (Grid, StopSign) {
+        let map  = AsciiMap::parse(STRAIGHT).unwrap();
+        let grid = Grid::new(map.tiles, Vec::new());
+        let sign = StopSign::new(Intersection::new(&grid.tiles, Point2::new(2, 2)).unwrap());
+
+        (grid, sign)
+    }
+
+    fn stopped(grid: &Grid, pos: (i32, i32)) -> Vehicle {
+        Vehicle::new(Point2::new(pos.0 as f32, pos.1 as f32) * TILE_SIZE_F, 8.0, 0.0, grid)
+    }
+
+    fn crossing(grid: &Grid, pos: (i32, i32), tile: (i32, i32)) -> Vehicle {
+        let mut vehicle = stopped(grid, pos);
+
+        vehicle.tile_pos = Point2::new(tile.0, tile.1);
+        vehicle.entry    = Some(vehicle.dir.out_dir());
+
+        vehicle
+    }
+
+    fn blocked(sign: &StopSign, vehicle: &Vehicle) -> bool {
+        let approach =
+            sign.intersection.approaches
+                .iter()
+                .find(|approach| approach.dir == vehicle.dir.out_dir())
+                .unwrap();
+
+        sign.blocks(vehicle, &approach.stop_line)
+    }
+
+    #[test]
+    fn releases_in_arrival_order() {
+        let (grid, mut sign) = setup();
+
+        let blocker = crossing(&grid, (4, 2), (2, 2));
+        let south   = stopped(&grid, (2, 1));
+        let east    = stopped(&grid, (1, 3));
+
+        sign.update(1, &[blocker, south]);
+        sign.update(2, &[blocker, south, east]);
+
+        assert!(blocked(&sign, &south));
+        assert!(blocked(&sign, &east));
+        assert_eq!(sign.arrivals().iter().map(|arrival| arrival.id).collect::<Vec<_>>(), [south.id, east.id]);
+
+        sign.update(3, &[south, east]);
+
+        assert!(!blocked(&sign, &south));
+        assert!(blocked(&sign, &east));
+    }
+
+    #[test]
+    fn yields_to_the_right_on_simultaneous_arrival() {
+        let (grid, mut sign) = setup();
+
+        let blocker = crossing(&grid, (4, 2), (2, 2));
+        let south   = stopped(&grid, (2, 1));
+        let east    = stopped(&grid, (1, 3));
+
+        sign.update(1, &[blocker, south, east]);
+        sign.update(2, &[south, east]);
+
+        assert!(!blocked(&sign, &east));
+        assert!(blocked(&sign, &south));
+    }
+
+    #[test]
+    fn releases_non_conflicting_approaches_together() {
+        let (grid, mut sign) = setup();
+
+        let south = stopped(&grid, (2, 1));
+        let north = stopped(&grid, (3, 4));
+        let west  = stopped(&grid, (4, 2));
+
+        sign.update(1, &[south, north]);
+
+        assert!(!blocked(&sign, &south));
+        assert!(!blocked(&sign, &north));
 
-        offsets.iter().any(|offset| {
-            let f_pos = self.pos + offset;
-            let pos   = Point2::new(f_pos.x as i32, f_pos.y as i32);
+        sign.update(2, &[south, north, west]);
 
-            vehicles.iter().any(|vehicle| vehicle.tile_pos == pos)
-        })
+        assert!(blocked(&sign, &west));
     }
 }
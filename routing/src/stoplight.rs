@@ -1,24 +1,123 @@
-use crate::dir_bounds::DirBounds;
-use crate::direction::Axis;
+use crate::bounds::Bounds;
+use crate::direction::{Axis, Cardinal};
+use crate::intersection::Intersection;
+use crate::traffic_control::{ControlState, SignalHead, TrafficControl};
+use crate::vehicle::Vehicle;
 
-use parry2d::na::Point2;
+use anyhow::{bail, Result};
 
 pub const GRACE_TIME: f32 = 2.5;
+pub const TURN_GAP:   f32 = 3.0;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Signal {
+    Green,
+    Yellow,
+    Red,
+}
+
+#[derive(Debug, Clone)]
+pub struct Phase {
+    pub approaches: Vec<Cardinal>,
+    pub duration:   f32,
+}
+
+impl Phase {
+    pub fn new(approaches: Vec<Cardinal>, duration: f32) -> Self {
+        Self {
+            approaches,
+            duration,
+        }
+    }
+}
 
 pub struct Stoplight {
-    pub pos: Point2<f32>,
-    freq: f32,
-    pub axis: Axis,
-    pub grace: Option<u32>,
+    pub intersection: Intersection,
+
+    pub phases: Vec<Phase>,
+    pub phase:  usize,
+    pub grace:  Option<u32>,
+    pub hold:   bool,
+    elapsed:    u32,
+
+    pub right_on_red: bool,
+    pub turn_gap:     f32,
+
+    oncoming:  Vec<Cardinal>,
+    inside:    Vec<Cardinal>,
+    spillback: Vec<u32>,
+    stopped:   Vec<u32>,
+    released:  Vec<u32>,
 }
 
 impl Stoplight {
-    pub fn new(pos: Point2<f32>, freq: f32) -> Self {
+    pub fn new(intersection: Intersection, freq: f32) -> Result<Self> {
+        let phases =
+            [Axis::Vertical, Axis::Horizontal]
+                .into_iter()
+                .map(|axis| {
+                    let approaches =
+                        intersection
+                            .dirs()
+                            .into_iter()
+                            .filter(|dir| dir.on_axis(&axis))
+                            .collect();
+
+                    Phase::new(approaches, freq)
+                })
+                .filter(|phase: &Phase| !phase.approaches.is_empty())
+                .collect();
+
+        Self::with_phases(intersection, phases)
+    }
+
+    pub fn with_phases(intersection: Intersection, phases: Vec<Phase>) -> Result<Self> {
+        if phases.is_empty() {
+            bail!("stoplight needs at least one phase");
+        }
+
+        let dirs = intersection.dirs();
+
+        for phase in &phases {
+            for &dir in &phase.approaches {
+                if !dirs.contains(&dir) {
+                    bail!("no approach heading {dir:?} in intersection");
+                }
+
+                let conflicting =
+                    phase.approaches
+                        .iter()
+                        .any(|&other| {
+                            other != dir.opposite() &&
+                            intersection.conflicting(dir, other)
+                        });
+
+                if conflicting {
+                    bail!("phase serves conflicting approaches {:?}", phase.approaches);
+                }
+            }
+        }
+
+        Ok(Self::from_phases(intersection, phases))
+    }
+
+    fn from_phases(intersection: Intersection, phases: Vec<Phase>) -> Self {
         Self {
-            pos,
-            freq,
-            axis:  Axis::Vertical,
-            grace: None,
+            intersection,
+            phases,
+            phase:   0,
+            grace:   None,
+            hold:    false,
+            elapsed: 0,
+
+            right_on_red: false,
+            turn_gap:     TURN_GAP,
+
+            oncoming:  Vec::new(),
+            inside:    Vec::new(),
+            spillback: Vec::new(),
+            stopped:   Vec::new(),
+            released:  Vec::new(),
         }
     }
 
@@ -26,20 +125,404 @@ impl Stoplight {
         self.grace.is_some()
     }
 
-    pub fn bounds(&self) -> DirBounds {
-        DirBounds::new(&self.pos)
+    pub fn remaining(&self) -> f32 {
+        match self.grace {
+            Some(_) => 0.0,
+            None    => self.phases[self.phase].duration - self.elapsed as f32 / 60.0,
+        }
+    }
+
+    pub fn clearance(&self) -> f32 {
+        match self.grace {
+            Some(period) => GRACE_TIME - period as f32 / 60.0,
+            None         => GRACE_TIME,
+        }
+    }
+
+    pub fn serving(&self, dir: Cardinal) -> bool {
+        self.phases[self.phase].approaches.contains(&dir)
+    }
+
+    fn green(&self, dir: Cardinal) -> bool {
+        !self.grace_period() && self.serving(dir)
+    }
+
+    pub fn signal(&self, dir: Cardinal) -> Signal {
+        match self.grace {
+            None if self.serving(dir) => Signal::Green,
+
+            Some(period) if self.serving(dir) && period < ((GRACE_TIME - 1.0) * 60.0) as u32
+                => Signal::Yellow,
+
+            _ => Signal::Red,
+        }
+    }
+
+    fn clear(&self, vehicles: &[Vehicle], dir: Cardinal) -> bool {
+        let inside = self.intersection.occupied(vehicles, dir);
+
+        let gap =
+            if self.green(dir) {
+                self.intersection.gap(vehicles, dir)
+            } else {
+                f32::INFINITY
+            };
+
+        !inside && gap >= self.turn_gap
     }
 
-    pub fn update(&mut self, time: u32) {
+    fn update_turns(&mut self, vehicles: &[Vehicle]) {
+        let approaching = self.intersection.approaching(vehicles);
+
+        self.stopped.retain(|id| approaching.contains(id));
+        self.released.retain(|id| approaching.contains(id));
+
+        self.oncoming =
+            self.intersection
+                .dirs()
+                .into_iter()
+                .filter(|&dir| !self.clear(vehicles, dir))
+                .collect();
+
+        self.inside    = self.intersection.entries(vehicles);
+        self.spillback = self.intersection.spillback(vehicles);
+
+        if !self.right_on_red {
+            return;
+        }
+
+        for approach in &self.intersection.approaches {
+            let dir = approach.dir;
+
+            if self.green(dir) {
+                continue;
+            }
+
+            let Some(vehicle) = approach.waiting(vehicles) else {
+                continue;
+            };
+
+            let right_turn =
+                vehicle.next.is_some_and(|next| next.is_right_turn());
+
+            if !right_turn || self.released.contains(&vehicle.id) {
+                continue;
+            }
+
+            if vehicle.speed() == 0.0 && !self.stopped.contains(&vehicle.id) {
+                self.stopped.push(vehicle.id);
+            }
+
+            let cross = dir.opposite().counter_rotate();
+
+            if self.stopped.contains(&vehicle.id) && !self.oncoming.contains(&cross) {
+                self.released.push(vehicle.id);
+            }
+        }
+    }
+}
+
+impl TrafficControl for Stoplight {
+    fn update(&mut self, _time: u32, vehicles: &[Vehicle]) {
         if let Some(period) = self.grace {
             if period >= (GRACE_TIME * 60.0) as u32 {
-                self.grace = None;
-                self.axis  = self.axis.flip();
+                self.grace   = None;
+                self.phase   = (self.phase + 1) % self.phases.len();
+                self.elapsed = 0;
             } else {
                 *self.grace.as_mut().unwrap() += 1;
             }
-        } else if time as f32 % (self.freq * 60.0) == 0.0 {
+        } else if !self.hold {
+            if self.elapsed >= (self.phases[self.phase].duration * 60.0) as u32 {
+                self.grace = Some(0);
+            } else {
+                self.elapsed += 1;
+            }
+        }
+
+        self.update_turns(vehicles);
+    }
+
+    fn blocks(&self, vehicle: &Vehicle, collider: &dyn Bounds) -> bool {
+        let heading = vehicle.entry.unwrap_or(vehicle.dir.out_dir());
+
+        let yielding =
+            vehicle.turning_left() &&
+            self.oncoming.contains(&heading.opposite());
+
+        if self.intersection.contains(vehicle.tile_pos) {
+            return yielding;
+        }
+
+        if self.spillback.contains(&vehicle.id) {
+            self.intersection.colliding(collider, |approach| approach.dir == heading)
+        } else if self.released.contains(&vehicle.id) {
+            false
+        } else if self.grace_period() {
+            self.intersection.colliding(collider, |_| true)
+        } else if self.green(heading) {
+            let clearing =
+                self.inside
+                    .iter()
+                    .any(|&other| {
+                        other != heading.opposite() &&
+                        self.intersection.conflicting(heading, other)
+                    });
+
+            let opposing =
+                vehicle.turning_left() &&
+                self.inside.contains(&heading.opposite());
+
+            (yielding || clearing || opposing) &&
+                self.intersection.colliding(collider, |approach| approach.dir == heading)
+        } else {
+            self.intersection.colliding(collider, |approach| !self.green(approach.dir))
+        }
+    }
+
+    fn state(&self) -> ControlState {
+        let heads =
+            self.intersection.approaches
+                .iter()
+                .map(|approach| SignalHead {
+                    dir:    approach.dir,
+                    line:   approach.stop_line.clone(),
+                    signal: self.signal(approach.dir),
+                })
+                .collect();
+
+        ControlState::Stoplight {
+            heads,
+            phase:     self.phase,
+            remaining: self.remaining(),
+            clearance: self.clearance(),
+            held:      self.hold,
+        }
+    }
+
+    fn intersection(&self) -> &Intersection {
+        &self.intersection
+    }
+
+    fn advance_phase(&mut self) {
+        if self.grace.is_none() {
             self.grace = Some(0);
         }
     }
+
+    fn hold_phase(&mut self, hold: bool) {
+        self.hold = hold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii_map::AsciiMap;
+    use crate::direction::Direction;
+    use crate::grid::Grid;
+    use crate::tile_map::TILE_SIZE_F;
+
+    use parry2d::na::Point2;
+
+    const CROSSING: &str = "  v^
+  v^
+<<++<<
+>>++>>
+  v^
+  v^
+";
+
+    const ONE_WAY: &str = " v
+<+<
+ v
+";
+
+    fn setup() -> (Grid, Stoplight) {
+        let map          = AsciiMap::parse(CROSSING).unwrap();
+        let grid         = Grid::new(map.tiles, Vec::new());
+        let intersection = Intersection::new(&grid.tiles, Point2::new(2, 2)).unwrap();
+
+        (grid, Stoplight::new(intersection, 10.0).unwrap())
+    }
+
+    fn vehicle(grid: &Grid, pos: (i32, i32), speed: f32, exit: Cardinal) -> Vehicle {
+        let mut vehicle = Vehicle::new(Point2::new(pos.0 as f32, pos.1 as f32) * TILE_SIZE_F, 8.0, speed, grid);
+
+        let heading = vehicle.dir.out_dir();
+
+        vehicle.intent = Some(exit);
+        vehicle.next   = Some(Direction::Straight(heading).towards(exit));
+
+        vehicle
+    }
+
+    fn blocked(light: &Stoplight, vehicle: &Vehicle) -> bool {
+        let approach =
+            light.intersection.approaches
+                .iter()
+                .find(|approach| approach.dir == vehicle.dir.out_dir())
+                .unwrap();
+
+        light.blocks(vehicle, &approach.stop_line)
+    }
+
+    #[test]
+    fn blocks_every_movement_on_red() {
+        let (grid, mut light) = setup();
+
+        let straight = vehicle(&grid, (1, 3), 0.0, Cardinal::Right);
+        let left     = vehicle(&grid, (1, 3), 0.0, Cardinal::Up);
+        let right    = vehicle(&grid, (1, 3), 0.0, Cardinal::Down);
+
+        light.update(1, &[straight]);
+
+        assert_eq!(light.signal(Cardinal::Right), Signal::Red);
+        assert!(blocked(&light, &straight));
+        assert!(blocked(&light, &left));
+        assert!(blocked(&light, &right));
+    }
+
+    #[test]
+    fn clears_every_movement_on_green() {
+        let (grid, mut light) = setup();
+
+        let straight = vehicle(&grid, (2, 1), 0.0, Cardinal::Down);
+        let left     = vehicle(&grid, (2, 1), 0.0, Cardinal::Right);
+        let right    = vehicle(&grid, (2, 1), 0.0, Cardinal::Left);
+
+        light.update(1, &[straight]);
+
+        assert_eq!(light.signal(Cardinal::Down), Signal::Green);
+        assert!(!blocked(&light, &straight));
+        assert!(!blocked(&light, &left));
+        assert!(!blocked(&light, &right));
+    }
+
+    #[test]
+    fn permissive_left_yields_to_oncoming_traffic() {
+        let (grid, mut light) = setup();
+
+        let left     = vehicle(&grid, (2, 1), 0.0, Cardinal::Right);
+        let straight = vehicle(&grid, (2, 1), 0.0, Cardinal::Down);
+        let oncoming = vehicle(&grid, (3, 5), 8.0, Cardinal::Up);
+
+        light.update(1, &[left, oncoming]);
+
+        assert!(blocked(&light, &left));
+        assert!(!blocked(&light, &straight));
+    }
+
+    #[test]
+    fn turns_right_on_red_after_stopping() {
+        let (grid, mut light) = setup();
+
+        let right = vehicle(&grid, (1, 3), 0.0, Cardinal::Down);
+
+        light.update(1, &[right]);
+
+        assert!(blocked(&light, &right));
+
+        light.right_on_red = true;
+        light.update(2, &[right]);
+
+        assert!(!blocked(&light, &right));
+    }
+
+    #[test]
+    fn holds_right_on_red_for_cross_traffic() {
+        let (grid, mut light) = setup();
+
+        light.right_on_red = true;
+
+        let right = vehicle(&grid, (1, 3), 0.0, Cardinal::Down);
+        let cross = vehicle(&grid, (2, 0), 8.0, Cardinal::Down);
+
+        light.update(1, &[right, cross]);
+
+        assert!(blocked(&light, &right));
+    }
+
+    #[test]
+    fn validates_phases() {
+        let map  = AsciiMap::parse(ONE_WAY).unwrap();
+        let grid = Grid::new(map.tiles, Vec::new());
+        let pos  = Point2::new(1, 1);
+
+        let phases = |phases: Vec<Phase>| {
+            Stoplight::with_phases(Intersection::new(&grid.tiles, pos).unwrap(), phases)
+        };
+
+        assert!(phases(Vec::new()).is_err());
+        assert!(phases(vec![Phase::new(vec![Cardinal::Up], 10.0)]).is_err());
+        assert!(phases(vec![Phase::new(vec![Cardinal::Down, Cardinal::Left], 10.0)]).is_err());
+
+        let light = phases(vec![
+            Phase::new(vec![Cardinal::Down], 10.0),
+            Phase::new(vec![Cardinal::Left], 10.0),
+        ]).unwrap();
+
+        assert_eq!(light.phases.len(), 2);
+        assert_eq!(Stoplight::new(Intersection::new(&grid.tiles, pos).unwrap(), 10.0).unwrap().phases.len(), 2);
+    }
+
+    #[test]
+    fn cycles_through_grace_to_the_next_phase() {
+        let (_, mut light) = setup();
+
+        light.phases[0].duration = 1.0;
+
+        for time in 1..=30 {
+            light.update(time, &[]);
+        }
+
+        assert_eq!(light.remaining(), 0.5);
+        assert_eq!(light.clearance(), GRACE_TIME);
+
+        for time in 31..=61 {
+            light.update(time, &[]);
+        }
+
+        assert!(light.grace_period());
+        assert_eq!(light.remaining(), 0.0);
+        assert_eq!(light.clearance(), GRACE_TIME);
+        assert_eq!(light.signal(Cardinal::Down), Signal::Yellow);
+        assert_eq!(light.signal(Cardinal::Left), Signal::Red);
+
+        for time in 62..=151 {
+            light.update(time, &[]);
+        }
+
+        assert_eq!(light.clearance(), 1.0);
+        assert_eq!(light.signal(Cardinal::Down), Signal::Red);
+
+        for time in 152..=212 {
+            light.update(time, &[]);
+        }
+
+        assert!(!light.grace_period());
+        assert_eq!(light.phase, 1);
+        assert_eq!(light.remaining(), 10.0);
+        assert_eq!(light.signal(Cardinal::Left), Signal::Green);
+        assert_eq!(light.signal(Cardinal::Down), Signal::Red);
+    }
+
+    #[test]
+    fn advances_and_holds_on_request() {
+        let (_, mut light) = setup();
+
+        light.hold_phase(true);
+
+        for time in 1..=1000 {
+            light.update(time, &[]);
+        }
+
+        assert_eq!(light.phase, 0);
+        assert_eq!(light.remaining(), 10.0);
+
+        light.advance_phase();
+
+        assert!(light.grace_period());
+        assert_eq!(light.signal(Cardinal::Down), Signal::Yellow);
+    }
 }
@@ -0,0 +1,692 @@
+use crate::direction::{Cardinal, TileDirection};
+use crate::grid::Grid;
+use crate::intersection::Intersection;
+use crate::priority_sign::PrioritySign;
+use crate::raster::{self, Road, METERS_PER_TILE, STAIR_STEP};
+use crate::stop_sign::StopSign;
+use crate::stoplight::{Phase, Stoplight};
+use crate::tile_map::TILE_SIZE_F;
+use crate::traffic_control::TrafficControl;
+use crate::vehicle::Vehicle;
+use crate::xml::{attr, optional_attr};
+
+use parry2d::na::Point2;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use anyhow::{bail, Context, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+pub const JUNCTION_RADIUS: i32 = 3;
+
+struct Edge {
+    id:       String,
+    from:     String,
+    to:       String,
+    priority: i32,
+    shape:    Vec<(f64, f64)>,
+}
+
+struct Junction {
+    id:   String,
+    kind: String,
+    pos:  (f64, f64),
+}
+
+struct Link {
+    tl:    String,
+    index: usize,
+    from:  String,
+}
+
+#[derive(Default)]
+struct Net {
+    edges:     Vec<Edge>,
+    junctions: Vec<Junction>,
+    programs:  HashMap<String, Vec<(f32, String)>>,
+    links:     Vec<Link>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Demand {
+    pub entry:  Point2<f32>,
+    pub begin:  u32,
+    pub end:    u32,
+    pub period: u32,
+}
+
+impl Demand {
+    pub fn due(&self, frame: u32) -> bool {
+        frame >= self.begin &&
+            frame <= self.end &&
+            (frame - self.begin) % self.period.max(1) == 0
+    }
+}
+
+pub struct SumoNet {
+    pub grid:  Grid,
+    pub edges: HashMap<String, Vec<Point2<i32>>>,
+}
+
+pub struct SumoImporter {
+    pub meters_per_tile: f64,
+    pub stair_step:      i32,
+    pub junction_radius: i32,
+    pub freq:            f32,
+}
+
+impl Default for SumoImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SumoImporter {
+    pub fn new() -> Self {
+        Self {
+            meters_per_tile: METERS_PER_TILE,
+            stair_step:      STAIR_STEP,
+            junction_radius: JUNCTION_RADIUS,
+            freq:            10.0,
+        }
+    }
+
+    pub fn import<P: AsRef<Path>>(&self, path: P) -> Result<SumoNet> {
+        let xml = fs::read_to_string(path).context("failed to read SUMO network")?;
+
+        self.parse(&xml)
+    }
+
+    pub fn parse(&self, xml: &str) -> Result<SumoNet> {
+        let net = read_net(xml)?;
+
+        if net.edges.is_empty() {
+            bail!("no edges in SUMO network");
+        }
+
+        let project = |(x, y): (f64, f64)| {
+            Point2::new(
+                (x / self.meters_per_tile).round() as i32,
+                (-y / self.meters_per_tile).round() as i32,
+            )
+        };
+
+        let junctions =
+            net.junctions
+                .iter()
+                .map(|junction| (junction.id.as_str(), junction))
+                .collect::<HashMap<_, _>>();
+
+        let mut roads = Vec::new();
+        let mut pairs: Vec<(usize, Option<usize>)> = Vec::new();
+
+        for (idx, edge) in net.edges.iter().enumerate() {
+            if pairs.iter().any(|&(forward, backward)| forward == idx || backward == Some(idx)) {
+                continue;
+            }
+
+            let reverse =
+                net.edges
+                    .iter()
+                    .enumerate()
+                    .position(|(other, candidate)| {
+                        other != idx &&
+                        candidate.from == edge.to &&
+                        candidate.to == edge.from &&
+                        !pairs.iter().any(|&(forward, backward)| forward == other || backward == Some(other))
+                    });
+
+            let mut points = Vec::new();
+
+            points.extend(junctions.get(edge.from.as_str()).map(|junction| junction.pos));
+            points.extend(edge.shape.iter().skip(1).take(edge.shape.len().saturating_sub(2)));
+            points.extend(junctions.get(edge.to.as_str()).map(|junction| junction.pos));
+
+            if points.len() < 2 {
+                points = edge.shape.clone();
+            }
+
+            roads.push(Road::new(points.into_iter().map(project).collect(), reverse.is_some()));
+            pairs.push((idx, reverse));
+        }
+
+        let (mut grid, lanes) = raster::rasterize(&roads, self.stair_step);
+
+        let edges =
+            lanes
+                .into_iter()
+                .filter_map(|lane| {
+                    let (forward, backward) = pairs[lane.road];
+                    let edge = if lane.forward { Some(forward) } else { backward }?;
+
+                    Some((net.edges[edge].id.clone(), lane.cells))
+                })
+                .collect::<HashMap<_, _>>();
+
+        let mut controls: Vec<Box<dyn TrafficControl>> = Vec::new();
+
+        for intersection in Intersection::all(&grid.tiles) {
+            let junction =
+                net.junctions
+                    .iter()
+                    .map(|junction| (intersection.distance(project(junction.pos)), junction))
+                    .filter(|&(distance, _)| distance <= self.junction_radius)
+                    .min_by_key(|&(distance, _)| distance)
+                    .map(|(_, junction)| junction);
+
+            if let Some(junction) = junction {
+                controls.extend(self.control(&net, &edges, intersection, junction)?);
+            }
+        }
+
+        grid.controls = controls;
+
+        Ok(SumoNet { grid, edges })
+    }
+
+    fn control(
+        &self,
+        net:          &Net,
+        edges:        &HashMap<String, Vec<Point2<i32>>>,
+        intersection: Intersection,
+        junction:     &Junction,
+    ) -> Result<Option<Box<dyn TrafficControl>>> {
+        let approach = |edge: &str| {
+            let cells = edges.get(edge)?;
+            let idx   = cells.iter().position(|&pos| intersection.contains(pos))?;
+
+            (idx > 0).then(|| raster::heading(cells[idx - 1], cells[idx]))
+        };
+
+        let incoming =
+            net.edges
+                .iter()
+                .filter(|edge| edge.to == junction.id)
+                .filter_map(|edge| Some((approach(&edge.id)?, edge.priority)))
+                .collect::<Vec<_>>();
+
+        let Some(top) = incoming.iter().map(|&(_, priority)| priority).max() else {
+            return Ok(None);
+        };
+
+        let minor =
+            incoming
+                .iter()
+                .filter(|&&(_, priority)| priority < top)
+                .map(|&(dir, _)| dir)
+                .collect::<Vec<_>>();
+
+        let control: Box<dyn TrafficControl> =
+            match junction.kind.as_str() {
+                "traffic_light" | "traffic_light_right_on_red" => {
+                    let mut stoplight =
+                        match net.programs.get(&junction.id) {
+                            Some(program) => {
+                                let phases =
+                                    program
+                                        .iter()
+                                        .filter_map(|(duration, state)| {
+                                            let approaches = phase_approaches(net, &junction.id, state, &approach);
+
+                                            (!approaches.is_empty()).then(|| Phase::new(approaches, *duration))
+                                        })
+                                        .collect();
+
+                                Stoplight::with_phases(intersection, phases)
+                                    .with_context(|| format!("invalid tlLogic for junction {}", junction.id))?
+                            }
+
+                            None => {
+                                Stoplight::new(intersection, self.freq)
+                                    .with_context(|| format!("no stoplight phases for junction {}", junction.id))?
+                            }
+                        };
+
+                    stoplight.right_on_red = junction.kind == "traffic_light_right_on_red";
+
+                    Box::new(stoplight)
+                }
+
+                "allway_stop" => Box::new(StopSign::new(intersection)),
+
+                "priority_stop" if !minor.is_empty() => Box::new(PrioritySign::two_way_stop(intersection, minor)),
+
+                "priority" if !minor.is_empty() => Box::new(PrioritySign::yield_sign(intersection, minor)),
+
+                _ => return Ok(None),
+            };
+
+        Ok(Some(control))
+    }
+}
+
+impl SumoNet {
+    pub fn import_routes<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Demand>> {
+        let xml = fs::read_to_string(path).context("failed to read SUMO routes")?;
+
+        self.parse_routes(&xml)
+    }
+
+    pub fn parse_routes(&self, xml: &str) -> Result<Vec<Demand>> {
+        let mut reader = Reader::from_str(xml);
+
+        let mut routes  = HashMap::new();
+        let mut demand  = Vec::new();
+        let mut pending: Option<(BytesStart<'static>, Option<String>)> = None;
+
+        loop {
+            let (start, empty) =
+                match reader.read_event().context("malformed SUMO routes")? {
+                    Event::Start(start) => (start.into_owned(), false),
+                    Event::Empty(start) => (start.into_owned(), true),
+
+                    Event::End(end) => {
+                        if matches!(end.name().as_ref(), b"vehicle" | b"flow" | b"trip") {
+                            if let Some((element, first)) = pending.take() {
+                                demand.push(self.demand(&element, first, &routes)?);
+                            }
+                        }
+
+                        continue;
+                    }
+
+                    Event::Eof => break,
+                    _          => continue,
+                };
+
+            match start.name().as_ref() {
+                b"route" => {
+                    let edges = attr(&start, "edges")?;
+                    let first = edges.split_whitespace().next().map(String::from);
+
+                    match &mut pending {
+                        Some((_, inline)) => *inline = first,
+                        None              => {
+                            if let Some(id) = optional_attr(&start, "id")? {
+                                routes.insert(id, first);
+                            }
+                        }
+                    }
+                }
+
+                b"vehicle" | b"flow" | b"trip" => {
+                    if empty {
+                        demand.push(self.demand(&start, None, &routes)?);
+                    } else {
+                        pending = Some((start, None));
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        Ok(demand)
+    }
+
+    fn demand(
+        &self,
+        element: &BytesStart,
+        inline:  Option<String>,
+        routes:  &HashMap<String, Option<String>>,
+    ) -> Result<Demand> {
+        let first =
+            match (inline, optional_attr(element, "route")?, optional_attr(element, "from")?) {
+                (Some(first), _, _) => Some(first),
+                (_, Some(route), _) => routes.get(&route).cloned().flatten(),
+                (_, _, Some(from))  => Some(from),
+                _                   => None,
+            }
+            .context("vehicle has no route")?;
+
+        let cells = self.edges.get(&first).with_context(|| format!("unknown edge {first}"))?;
+
+        let pos =
+            cells
+                .iter()
+                .find(|&pos| {
+                    self.grid.tiles
+                        .get(pos)
+                        .is_some_and(|tile| matches!(tile.dir, TileDirection::Constant(_)))
+                })
+                .with_context(|| format!("edge {first} has no road tiles to spawn on"))?;
+
+        let entry = Point2::new(pos.x as f32, pos.y as f32) * TILE_SIZE_F;
+
+        let seconds = |name: &str| -> Result<Option<f32>> {
+            Ok(optional_attr(element, name)?.and_then(|value| value.parse().ok()))
+        };
+
+        let frames = |seconds: f32| (seconds * 60.0).round().max(0.0) as u32;
+
+        if element.name().as_ref() != b"flow" {
+            let depart = frames(seconds("depart")?.unwrap_or(0.0));
+
+            return Ok(Demand { entry, begin: depart, end: depart, period: 1 });
+        }
+
+        let begin = seconds("begin")?.unwrap_or(0.0);
+        let end   = seconds("end")?.unwrap_or(3600.0);
+
+        let period =
+            if let Some(period) = seconds("period")? {
+                period
+            } else if let Some(rate) = seconds("vehsPerHour")? {
+                3600.0 / rate
+            } else if let Some(probability) = seconds("probability")? {
+                1.0 / probability
+            } else if let Some(number) = seconds("number")? {
+                (end - begin) / number
+            } else {
+                bail!("flow has no rate");
+            };
+
+        Ok(Demand {
+            entry,
+            begin:  frames(begin),
+            end:    frames(end),
+            period: frames(period).max(1),
+        })
+    }
+}
+
+pub struct FcdWriter<W: Write> {
+    out: W,
+
+    pub meters_per_tile: f64,
+}
+
+impl<W: Write> FcdWriter<W> {
+    pub fn new(mut out: W) -> Result<Self> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(out, "<fcd-export>")?;
+
+        Ok(Self {
+            out,
+            meters_per_tile: METERS_PER_TILE,
+        })
+    }
+
+    pub fn timestep(&mut self, frame: u32, vehicles: &[Vehicle]) -> Result<()> {
+        let scale = self.meters_per_tile / TILE_SIZE_F as f64;
+
+        writeln!(self.out, r#"    <timestep time="{:.2}">"#, frame as f32 / 60.0)?;
+
+        for vehicle in vehicles {
+            let center = vehicle.center();
+
+            writeln!(
+                self.out,
+                r#"        <vehicle id="{}" x="{:.2}" y="{:.2}" angle="{:.2}" type="DEFAULT_VEHTYPE" speed="{:.2}"/>"#,
+                vehicle.id,
+                center.x as f64 * scale,
+                -center.y as f64 * scale,
+                vehicle.dir.degrees() % 360.0,
+                vehicle.speed() as f64 * scale,
+            )?;
+        }
+
+        writeln!(self.out, "    </timestep>")?;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        writeln!(self.out, "</fcd-export>")?;
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+}
+
+fn phase_approaches<F>(net: &Net, tl: &str, state: &str, approach: &F) -> Vec<Cardinal>
+where
+    F: Fn(&str) -> Option<Cardinal>,
+{
+    let signals = state.chars().collect::<Vec<_>>();
+
+    let serving = |green: char| {
+        let mut approaches = Vec::new();
+
+        for link in net.links.iter().filter(|link| link.tl == tl) {
+            if signals.get(link.index) != Some(&green) {
+                continue;
+            }
+
+            if let Some(dir) = approach(&link.from) {
+                if !approaches.contains(&dir) {
+                    approaches.push(dir);
+                }
+            }
+        }
+
+        approaches
+    };
+
+    let major = serving('G');
+
+    if major.is_empty() {
+        serving('g')
+    } else {
+        major
+    }
+}
+
+fn read_net(xml: &str) -> Result<Net> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut net     = Net::default();
+    let mut edge    = None;
+    let mut program = None;
+
+    loop {
+        let start =
+            match reader.read_event().context("malformed SUMO network")? {
+                Event::Start(start) | Event::Empty(start) => start,
+
+                Event::End(end) => {
+                    match end.name().as_ref() {
+                        b"edge"    => net.edges.extend(edge.take()),
+                        b"tlLogic" => {
+                            if let Some((id, phases)) = program.take() {
+                                net.programs.entry(id).or_insert(phases);
+                            }
+                        }
+
+                        _ => {}
+                    }
+
+                    continue;
+                }
+
+                Event::Eof => break,
+                _          => continue,
+            };
+
+        match start.name().as_ref() {
+            b"edge" => {
+                if optional_attr(&start, "function")?.is_some_and(|function| function != "normal") {
+                    continue;
+                }
+
+                edge = Some(Edge {
+                    id:       attr(&start, "id")?,
+                    from:     attr(&start, "from")?,
+                    to:       attr(&start, "to")?,
+                    priority: optional_attr(&start, "priority")?.and_then(|value| value.parse().ok()).unwrap_or(-1),
+                    shape:    optional_attr(&start, "shape")?.map(|shape| parse_shape(&shape)).transpose()?.unwrap_or_default(),
+                });
+            }
+
+            b"lane" => {
+                if let Some(edge) = edge.as_mut().filter(|edge| edge.shape.is_empty()) {
+                    edge.shape = parse_shape(&attr(&start, "shape")?)?;
+                }
+            }
+
+            b"junction" => {
+                let kind = attr(&start, "type")?;
+
+                if kind != "internal" {
+                    net.junctions.push(Junction {
+                        id:  attr(&start, "id")?,
+                        pos: (attr(&start, "x")?.parse()?, attr(&start, "y")?.parse()?),
+                        kind,
+                    });
+                }
+            }
+
+            b"tlLogic" => {
+                program = Some((attr(&start, "id")?, Vec::new()));
+            }
+
+            b"phase" => {
+                if let Some((_, phases)) = &mut program {
+                    phases.push((attr(&start, "duration")?.parse()?, attr(&start, "state")?));
+                }
+            }
+
+            b"connection" => {
+                if let (Some(tl), Some(index)) = (optional_attr(&start, "tl")?, optional_attr(&start, "linkIndex")?) {
+                    net.links.push(Link {
+                        tl,
+                        index: index.parse()?,
+                        from:  attr(&start, "from")?,
+                    });
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(net)
+}
+
+fn parse_shape(shape: &str) -> Result<Vec<(f64, f64)>> {
+    shape
+        .split_whitespace()
+        .map(|point| {
+            let mut coords = point.split(',');
+
+            let x = coords.next().context("malformed shape point")?;
+            let y = coords.next().context("malformed shape point")?;
+
+            Ok((x.parse()?, y.parse()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::stoplight::Signal;
+    use crate::traffic_control::ControlState;
+
+    fn net(first: &str, second: &str) -> String {
+        format!(r#"<net>
+  <edge id="WC" from="W" to="C" priority="2"><lane id="WC_0" shape="-100,0 0,0"/></edge>
+  <edge id="CW" from="C" to="W" priority="2"><lane id="CW_0" shape="0,0 -100,0"/></edge>
+  <edge id="EC" from="E" to="C" priority="2"><lane id="EC_0" shape="100,0 0,0"/></edge>
+  <edge id="CE" from="C" to="E" priority="2"><lane id="CE_0" shape="0,0 100,0"/></edge>
+  <edge id="NC" from="N" to="C" priority="1"><lane id="NC_0" shape="0,100 0,0"/></edge>
+  <edge id="CS" from="C" to="S" priority="1"><lane id="CS_0" shape="0,0 0,-100"/></edge>
+  <edge id=":C_0" function="internal"><lane id=":C_0_0" shape="0,0 1,1"/></edge>
+  <tlLogic id="C" type="static" programID="0" offset="0">
+    <phase duration="20" state="{first}"/>
+    <phase duration="10" state="{second}"/>
+  </tlLogic>
+  <junction id="W" type="dead_end" x="-100" y="0"/>
+  <junction id="E" type="dead_end" x="100" y="0"/>
+  <junction id="N" type="dead_end" x="0" y="100"/>
+  <junction id="S" type="dead_end" x="0" y="-100"/>
+  <junction id="C" type="traffic_light" x="0" y="0"/>
+  <connection from="WC" to="CE" tl="C" linkIndex="0"/>
+  <connection from="EC" to="CW" tl="C" linkIndex="1"/>
+  <connection from="NC" to="CS" tl="C" linkIndex="2"/>
+</net>
+"#)
+    }
+
+    const ROUTES: &str = r#"<routes>
+  <route id="r0" edges="WC CE"/>
+  <vehicle id="v0" route="r0" depart="1"/>
+  <flow id="f0" from="NC" to="CS" begin="0" end="20" period="2"/>
+  <flow id="f1" begin="0" end="20" vehsPerHour="900"><route edges="EC CW"/></flow>
+</routes>
+"#;
+
+    #[test]
+    fn imports_edges_and_signal_program() {
+        let net = SumoImporter::new().parse(&net("GGr", "rrG")).unwrap();
+
+        let mut edges = net.edges.keys().cloned().collect::<Vec<_>>();
+        edges.sort();
+
+        assert_eq!(edges, ["CE", "CS", "CW", "EC", "NC", "WC"]);
+        assert_eq!(net.grid.clusters.len(), 1);
+        assert_eq!(net.grid.controls.len(), 1);
+
+        let ControlState::Stoplight { heads, phase, .. } = net.grid.controls[0].state() else {
+            panic!("expected a stoplight");
+        };
+
+        assert_eq!(phase, 0);
+
+        for head in heads {
+            let expected = if head.dir == Cardinal::Down { Signal::Red } else { Signal::Green };
+
+            assert_eq!(head.signal, expected, "{:?}", head.dir);
+        }
+    }
+
+    #[test]
+    fn reports_invalid_programs() {
+        let err = SumoImporter::new().parse(&net("rrr", "rrr")).err().unwrap();
+
+        assert!(format!("{err:#}").contains("junction C"), "{err:#}");
+    }
+
+    #[test]
+    fn reads_vehicles_and_flows() {
+        let net    = SumoImporter::new().parse(&net("GGr", "rrG")).unwrap();
+        let demand = net.parse_routes(ROUTES).unwrap();
+
+        let entry = |edge: &str| {
+            let pos = net.edges[edge][0];
+
+            Point2::new(pos.x as f32, pos.y as f32) * TILE_SIZE_F
+        };
+
+        assert_eq!(demand, [
+            Demand { entry: entry("WC"), begin: 60, end: 60,   period: 1   },
+            Demand { entry: entry("NC"), begin: 0,  end: 1200, period: 120 },
+            Demand { entry: entry("EC"), begin: 0,  end: 1200, period: 240 },
+        ]);
+
+        assert!(demand[0].due(60) && !demand[0].due(61));
+        assert!(demand[2].due(480) && !demand[2].due(481) && !demand[2].due(1440));
+        assert!(net.parse_routes(r#"<routes><vehicle id="v" route="missing"/></routes>"#).is_err());
+    }
+
+    #[test]
+    fn writes_fcd_timesteps() {
+        let net     = SumoImporter::new().parse(&net("GGr", "rrG")).unwrap();
+        let demand  = net.parse_routes(ROUTES).unwrap();
+        let vehicle = Vehicle::new(demand[0].entry, 8.0, 2.0, &net.grid);
+
+        let mut fcd = FcdWriter::new(Vec::new()).unwrap();
+        fcd.timestep(30, &[vehicle]).unwrap();
+
+        let xml = String::from_utf8(fcd.finish().unwrap()).unwrap();
+
+        assert!(xml.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(xml.contains(r#"<timestep time="0.50">"#));
+        assert!(xml.contains(r#"angle="90.00""#), "{xml}");
+        assert!(xml.trim_end().ends_with("</fcd-export>"));
+    }
+}
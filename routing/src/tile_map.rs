@@ -1,5 +1,7 @@
 use parry2d::na::Point2;
 
+use std::collections::HashMap;
+
 pub const TILE_SIZE:   i32 = 15;
 pub const TILE_SIZE_F: f32 = TILE_SIZE as f32;
 
@@ -9,22 +11,44 @@ pub trait Locatable {
 
 pub struct TileMap<T: Locatable> {
     pub tiles: Vec<T>,
+    index:     HashMap<Point2<i32>, usize>,
 }
 
 impl<T: Locatable> TileMap<T> {
     pub fn new(tiles: Vec<T>) -> Self {
-        Self { tiles }
+        let mut index = HashMap::with_capacity(tiles.len());
+
+        for (idx, tile) in tiles.iter().enumerate() {
+            index.entry(*tile.pos()).or_insert(idx);
+        }
+
+        Self { tiles, index }
+    }
+
+    pub fn get(&self, pos: &Point2<i32>) -> Option<&T> {
+        self.index.get(pos).map(|&idx| &self.tiles[idx])
     }
 
-     pub fn at_pos(&self, pos: &Point2<f32>) -> Option<&T> {
-        self.tiles.iter().find(|tile| {
-            let x = tile.pos().x as f32 * TILE_SIZE_F;
-            let y = tile.pos().y as f32 * TILE_SIZE_F;
+    pub fn at_pos(&self, pos: &Point2<f32>) -> Option<&T> {
+        let x = pos.x / TILE_SIZE_F;
+        let y = pos.y / TILE_SIZE_F;
 
-            pos.x <= x + (TILE_SIZE_F / 2.0) &&
-                pos.x >= x - (TILE_SIZE_F / 2.0) &&
-                pos.y <= y + (TILE_SIZE_F / 2.0) &&
-                pos.y >= y - (TILE_SIZE_F / 2.0)
-        })
+        [x.floor(), x.ceil()]
+            .into_iter()
+            .flat_map(|x| [y.floor(), y.ceil()].map(|y| Point2::new(x as i32, y as i32)))
+            .filter_map(|candidate| self.index.get(&candidate).copied())
+            .filter(|&idx| contains(self.tiles[idx].pos(), pos))
+            .min()
+            .map(|idx| &self.tiles[idx])
     }
 }
+
+pub fn contains(tile_pos: &Point2<i32>, pos: &Point2<f32>) -> bool {
+    let x = tile_pos.x as f32 * TILE_SIZE_F;
+    let y = tile_pos.y as f32 * TILE_SIZE_F;
+
+    pos.x <= x + (TILE_SIZE_F / 2.0) &&
+        pos.x >= x - (TILE_SIZE_F / 2.0) &&
+        pos.y <= y + (TILE_SIZE_F / 2.0) &&
+        pos.y >= y - (TILE_SIZE_F / 2.0)
+}
@@ -0,0 +1,811 @@
+use crate::ascii_map::{self, Marker};
+use crate::direction::{Cardinal, Direction, TileDirection};
+use crate::grid::Grid;
+use crate::intersection::Intersection;
+use crate::raster;
+use crate::road_grid;
+use crate::tile::Tile;
+use crate::tile_map::{TileMap, TILE_SIZE_F};
+use crate::traffic_control::TrafficControl;
+use crate::xml::{attr, optional_attr};
+
+use parry2d::na::{Point2, Vector2};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use serde_json::Value;
+use anyhow::{bail, Context, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const MARKER_RADIUS: i32 = 1;
+
+const FLIP_HORIZONTAL: u32 = 0x8000_0000;
+const FLIP_VERTICAL:   u32 = 0x4000_0000;
+const FLIP_DIAGONAL:   u32 = 0x2000_0000;
+const ROTATE_HEX:      u32 = 0x1000_0000;
+
+const DEFAULT_KINDS: [(u32, TileKind); 4] = [
+    (1, TileKind::Straight),
+    (2, TileKind::RightTurn),
+    (3, TileKind::LeftTurn),
+    (4, TileKind::Intersection),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TileKind {
+    Straight,
+    RightTurn,
+    LeftTurn,
+    Intersection,
+}
+
+impl TileKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "straight"            => Some(Self::Straight),
+            "turn" | "right_turn" => Some(Self::RightTurn),
+            "left_turn"           => Some(Self::LeftTurn),
+            "intersection"        => Some(Self::Intersection),
+            _                     => None,
+        }
+    }
+
+    fn direction(&self) -> Option<Direction> {
+        match self {
+            Self::Straight     => Some(Direction::Straight(Cardinal::Up)),
+            Self::RightTurn    => Some(Direction::Turn(Cardinal::Up, Cardinal::Right)),
+            Self::LeftTurn     => Some(Direction::Turn(Cardinal::Up, Cardinal::Left)),
+            Self::Intersection => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ObjectKind {
+    Marker(Marker),
+    Spawn,
+}
+
+impl ObjectKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "stoplight" => Some(Self::Marker(Marker::Stoplight)),
+            "stop_sign" => Some(Self::Marker(Marker::StopSign)),
+            "spawn"     => Some(Self::Spawn),
+            _           => None,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct TileDef {
+    kind:     Option<TileKind>,
+    rotation: i32,
+}
+
+#[derive(Default)]
+struct Tileset {
+    first: u32,
+    defs:  HashMap<u32, TileDef>,
+}
+
+struct Object {
+    class: String,
+    pos:   (f64, f64),
+}
+
+struct Data {
+    width:  i32,
+    origin: Point2<i32>,
+    index:  usize,
+    shown:  bool,
+}
+
+#[derive(Default)]
+struct Document {
+    tile_size: (f64, f64),
+    tilesets:  Vec<Tileset>,
+    cells:     Vec<(Point2<i32>, u32)>,
+    objects:   Vec<Object>,
+}
+
+impl Document {
+    fn cell(&mut self, data: &mut Data, gid: u32) {
+        let idx   = data.index as i32;
+        let width = data.width.max(1);
+
+        if data.shown {
+            self.cells.push((data.origin + Vector2::new(idx % width, idx / width), gid));
+        }
+
+        data.index += 1;
+    }
+}
+
+pub struct TiledMap {
+    pub tiles:   TileMap<Tile>,
+    pub markers: HashMap<Point2<i32>, Marker>,
+    pub spawns:  Vec<Point2<f32>>,
+}
+
+impl TiledMap {
+    pub fn controls(&self) -> Result<Vec<Box<dyn TrafficControl>>> {
+        ascii_map::controls(&self.tiles, &self.markers)
+    }
+
+    pub fn grid(self) -> Result<Grid> {
+        let controls = self.controls()?;
+
+        let mut grid = Grid::new(self.tiles, controls);
+
+        if !self.spawns.is_empty() {
+            grid.entries = self.spawns;
+        }
+
+        Ok(grid)
+    }
+}
+
+pub struct TiledImporter {
+    pub kinds:         HashMap<u32, TileKind>,
+    pub marker_radius: i32,
+}
+
+impl Default for TiledImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TiledImporter {
+    pub fn new() -> Self {
+        Self {
+            kinds:         DEFAULT_KINDS.into_iter().collect(),
+            marker_radius: MARKER_RADIUS,
+        }
+    }
+
+    pub fn import<P: AsRef<Path>>(&self, path: P) -> Result<TiledMap> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).context("failed to read Tiled map")?;
+        let dir  = path.parent().unwrap_or(Path::new(""));
+
+        let resolve = |source: &str| {
+            fs::read_to_string(dir.join(source)).with_context(|| format!("failed to read tileset {source}"))
+        };
+
+        let document =
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("tmx")          => read_tmx(&text, &resolve)?,
+                Some("tmj" | "json") => read_tmj(&text, &resolve)?,
+                _                    => bail!("unknown Tiled map format {}", path.display()),
+            };
+
+        self.build(document)
+    }
+
+    pub fn parse_tmx(&self, xml: &str) -> Result<TiledMap> {
+        self.build(read_tmx(xml, &embedded)?)
+    }
+
+    pub fn parse_tmj(&self, json: &str) -> Result<TiledMap> {
+        self.build(read_tmj(json, &embedded)?)
+    }
+
+    fn build(&self, document: Document) -> Result<TiledMap> {
+        let cells =
+            document.cells
+                .iter()
+                .filter_map(|&(pos, gid)| Some((pos, self.cell(&document.tilesets, gid)?)))
+                .collect::<HashMap<_, _>>();
+
+        if cells.is_empty() {
+            bail!("no road tiles in Tiled map");
+        }
+
+        let tiles         = road_grid::resolve(&cells)?;
+        let intersections = Intersection::all(&tiles);
+
+        let mut markers = HashMap::new();
+        let mut spawns  = Vec::new();
+
+        let (width, height) = document.tile_size;
+
+        for object in &document.objects {
+            let Some(kind) = ObjectKind::parse(&object.class) else {
+                continue;
+            };
+
+            let pos = Point2::new(
+                (object.pos.0 / width).floor() as i32,
+                (object.pos.1 / height).floor() as i32,
+            );
+
+            match kind {
+                ObjectKind::Marker(marker) => {
+                    let intersection =
+                        intersections
+                            .iter()
+                            .map(|intersection| (intersection.distance(pos), intersection))
+                            .filter(|&(distance, _)| distance <= self.marker_radius)
+                            .min_by_key(|&(distance, _)| distance)
+                            .map(|(_, intersection)| intersection)
+                            .with_context(|| format!("{} at {pos:?} is not next to an intersection", object.class))?;
+
+                    let key = intersection.tiles[0];
+
+                    if markers.insert(key, marker).is_some_and(|previous| previous != marker) {
+                        bail!("intersection at {key:?} has mixed control markers");
+                    }
+                }
+
+                ObjectKind::Spawn => {
+                    match tiles.get(&pos).map(|tile| &tile.dir) {
+                        Some(TileDirection::Constant(_)) => {
+                            spawns.push(Point2::new(pos.x as f32, pos.y as f32) * TILE_SIZE_F);
+                        }
+
+                        _ => bail!("spawn point at {pos:?} is not on a road tile"),
+                    }
+                }
+            }
+        }
+
+        Ok(TiledMap { tiles, markers, spawns })
+    }
+
+    fn cell(&self, tilesets: &[Tileset], raw: u32) -> Option<Option<Direction>> {
+        let gid = raw & !(FLIP_HORIZONTAL | FLIP_VERTICAL | FLIP_DIAGONAL | ROTATE_HEX);
+
+        if gid == 0 {
+            return None;
+        }
+
+        let def =
+            tilesets
+                .iter()
+                .filter(|tileset| tileset.first <= gid)
+                .max_by_key(|tileset| tileset.first)
+                .and_then(|tileset| tileset.defs.get(&(gid - tileset.first)).copied())
+                .unwrap_or_default();
+
+        let kind = def.kind.or_else(|| self.kinds.get(&gid).copied())?;
+
+        Some(kind.direction().map(|dir| orient(dir, def.rotation, raw)))
+    }
+}
+
+fn embedded(source: &str) -> Result<String> {
+    bail!("external tileset {source} can only be loaded when importing from a file")
+}
+
+fn orient(dir: Direction, rotation: i32, flags: u32) -> Direction {
+    let transform = |mut cardinal: Cardinal| {
+        for _ in 0..rotation.rem_euclid(360) / 90 {
+            cardinal = cardinal.rotate();
+        }
+
+        let mut offset = cardinal.offset();
+
+        if flags & FLIP_DIAGONAL != 0 {
+            offset = Vector2::new(offset.y, offset.x);
+        }
+
+        if flags & FLIP_HORIZONTAL != 0 {
+            offset.x = -offset.x;
+        }
+
+        if flags & FLIP_VERTICAL != 0 {
+            offset.y = -offset.y;
+        }
+
+        raster::heading(Point2::origin(), Point2::from(offset))
+    };
+
+    match dir {
+        Direction::Straight(dir)         => Direction::Straight(transform(dir)),
+        Direction::Turn(in_dir, out_dir) => Direction::Turn(transform(in_dir), transform(out_dir)),
+    }
+}
+
+fn property(def: &mut TileDef, name: &str, value: &str) -> Result<()> {
+    match name {
+        "kind" => {
+            def.kind = Some(TileKind::parse(value).with_context(|| format!("unknown tile kind {value:?}"))?);
+        }
+
+        "rotation" => {
+            def.rotation = value.parse().with_context(|| format!("invalid tile rotation {value:?}"))?;
+
+            if def.rotation % 90 != 0 {
+                bail!("tile rotation {value} is not a multiple of 90 degrees");
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn load_tileset(source: &str, first: u32, resolve: &dyn Fn(&str) -> Result<String>) -> Result<Tileset> {
+    let text = resolve(source)?;
+
+    let mut tileset =
+        if source.ends_with(".tsx") {
+            read_tmx(&text, resolve)?
+                .tilesets
+                .pop()
+                .with_context(|| format!("tileset {source} is empty"))?
+        } else {
+            json_tileset(&serde_json::from_str(&text).context("malformed Tiled JSON tileset")?, first)?
+        };
+
+    tileset.first = first;
+
+    Ok(tileset)
+}
+
+fn read_tmx(xml: &str, resolve: &dyn Fn(&str) -> Result<String>) -> Result<Document> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut document = Document::default();
+    let mut visible  = Vec::new();
+    let mut width    = 0;
+    let mut tileset: Option<Tileset>       = None;
+    let mut tile:    Option<(u32, TileDef)> = None;
+    let mut data:    Option<Data>          = None;
+
+    loop {
+        let (start, empty) =
+            match reader.read_event().context("malformed Tiled XML")? {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+
+                Event::Text(text) => {
+                    if let Some(data) = &mut data {
+                        for gid in text.decode()?.split(',').map(str::trim).filter(|gid| !gid.is_empty()) {
+                            document.cell(data, gid.parse().context("invalid tile id in layer data")?);
+                        }
+                    }
+
+                    continue;
+                }
+
+                Event::End(end) => {
+                    match end.name().as_ref() {
+                        b"tile" => {
+                            if let (Some(tileset), Some((id, def))) = (&mut tileset, tile.take()) {
+                                tileset.defs.insert(id, def);
+                            }
+                        }
+
+                        b"tileset" => {
+                            document.tilesets.extend(tileset.take());
+                        }
+
+                        b"data" => {
+                            data = None;
+                        }
+
+                        b"layer" | b"objectgroup" | b"group" => {
+                            visible.pop();
+                        }
+
+                        _ => {}
+                    }
+
+                    continue;
+                }
+
+                Event::Eof => break,
+                _          => continue,
+            };
+
+        let shown = visible.iter().all(|&visible| visible);
+
+        match start.name().as_ref() {
+            b"map" => {
+                if let Some(orientation) = optional_attr(&start, "orientation")? {
+                    if orientation != "orthogonal" {
+                        bail!("{orientation} Tiled maps are not supported");
+                    }
+                }
+
+                document.tile_size = (
+                    attr(&start, "tilewidth")?.parse()?,
+                    attr(&start, "tileheight")?.parse()?,
+                );
+            }
+
+            b"tileset" => {
+                let first = optional_attr(&start, "firstgid")?.map(|first| first.parse()).transpose()?.unwrap_or(1);
+
+                if let Some(source) = optional_attr(&start, "source")? {
+                    document.tilesets.push(load_tileset(&source, first, resolve)?);
+                } else if empty {
+                    document.tilesets.push(Tileset { first, defs: HashMap::new() });
+                } else {
+                    tileset = Some(Tileset { first, defs: HashMap::new() });
+                }
+            }
+
+            b"tile" if data.is_some() => {
+                let gid = optional_attr(&start, "gid")?.map(|gid| gid.parse()).transpose()?.unwrap_or(0);
+
+                if let Some(data) = &mut data {
+                    document.cell(data, gid);
+                }
+            }
+
+            b"tile" => {
+                let class = optional_attr(&start, "class")?.or(optional_attr(&start, "type")?);
+
+                let def = TileDef {
+                    kind:     class.as_deref().and_then(TileKind::parse),
+                    rotation: 0,
+                };
+
+                let id = attr(&start, "id")?.parse()?;
+
+                match (&mut tileset, empty) {
+                    (Some(tileset), true) => {
+                        tileset.defs.insert(id, def);
+                    }
+
+                    _ => tile = Some((id, def)),
+                }
+            }
+
+            b"property" => {
+                if let Some((_, def)) = &mut tile {
+                    property(def, &attr(&start, "name")?, &attr(&start, "value")?)?;
+                }
+            }
+
+            b"layer" | b"objectgroup" | b"group" => {
+                if !empty {
+                    visible.push(optional_attr(&start, "visible")?.is_none_or(|visible| visible != "0"));
+                }
+
+                width = optional_attr(&start, "width")?.map(|width| width.parse()).transpose()?.unwrap_or(0);
+            }
+
+            b"data" if !empty => {
+                if optional_attr(&start, "encoding")?.is_some_and(|encoding| encoding == "base64") {
+                    bail!("base64 layer data is not supported, save the map with CSV encoding");
+                }
+
+                data = Some(Data { width, origin: Point2::origin(), index: 0, shown });
+            }
+
+            b"chunk" => {
+                if let Some(data) = &mut data {
+                    data.width  = attr(&start, "width")?.parse()?;
+                    data.origin = Point2::new(attr(&start, "x")?.parse()?, attr(&start, "y")?.parse()?);
+                    data.index  = 0;
+                }
+            }
+
+            b"object" if shown && tileset.is_none() => {
+                document.objects.push(tmx_object(&start)?);
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(document)
+}
+
+fn tmx_object(start: &BytesStart) -> Result<Object> {
+    let class =
+        optional_attr(start, "class")?
+            .or(optional_attr(start, "type")?)
+            .filter(|class| !class.is_empty())
+            .or(optional_attr(start, "name")?)
+            .unwrap_or_default();
+
+    let number = |name: &str| -> Result<f64> {
+        Ok(optional_attr(start, name)?.map(|value| value.parse()).transpose()?.unwrap_or(0.0))
+    };
+
+    let tile = optional_attr(start, "gid")?.is_some();
+
+    Ok(object(class, number("x")?, number("y")?, number("width")?, number("height")?, tile))
+}
+
+fn object(class: String, x: f64, y: f64, width: f64, height: f64, tile: bool) -> Object {
+    let top = if tile { y - height } else { y };
+
+    Object {
+        class,
+        pos: (x + width / 2.0, top + height / 2.0),
+    }
+}
+
+fn read_tmj(json: &str, resolve: &dyn Fn(&str) -> Result<String>) -> Result<Document> {
+    let map: Value = serde_json::from_str(json).context("malformed Tiled JSON")?;
+
+    if let Some(orientation) = map["orientation"].as_str() {
+        if orientation != "orthogonal" {
+            bail!("{orientation} Tiled maps are not supported");
+        }
+    }
+
+    let mut document = Document {
+        tile_size: (
+            map["tilewidth"].as_f64().context("map has no tilewidth")?,
+            map["tileheight"].as_f64().context("map has no tileheight")?,
+        ),
+        ..Document::default()
+    };
+
+    for tileset in map["tilesets"].as_array().into_iter().flatten() {
+        let first = tileset["firstgid"].as_u64().unwrap_or(1) as u32;
+
+        let tileset =
+            match tileset["source"].as_str() {
+                Some(source) => load_tileset(source, first, resolve)?,
+                None         => json_tileset(tileset, first)?,
+            };
+
+        document.tilesets.push(tileset);
+    }
+
+    json_layers(&map["layers"], &mut document)?;
+
+    Ok(document)
+}
+
+fn json_tileset(value: &Value, first: u32) -> Result<Tileset> {
+    let mut defs = HashMap::new();
+
+    for tile in value["tiles"].as_array().into_iter().flatten() {
+        let id = tile["id"].as_u64().context("tileset tile has no id")? as u32;
+
+        let mut def = TileDef {
+            kind:     tile["class"].as_str().or(tile["type"].as_str()).and_then(TileKind::parse),
+            rotation: 0,
+        };
+
+        for prop in tile["properties"].as_array().into_iter().flatten() {
+            let name = prop["name"].as_str().context("tile property has no name")?;
+
+            let value =
+                match &prop["value"] {
+                    Value::String(value) => value.clone(),
+                    value                => value.to_string(),
+                };
+
+            property(&mut def, name, &value)?;
+        }
+
+        defs.insert(id, def);
+    }
+
+    Ok(Tileset { first, defs })
+}
+
+fn json_layers(layers: &Value, document: &mut Document) -> Result<()> {
+    for layer in layers.as_array().into_iter().flatten() {
+        if layer["visible"].as_bool() == Some(false) {
+            continue;
+        }
+
+        match layer["type"].as_str() {
+            Some("tilelayer") => {
+                if layer["encoding"].as_str() == Some("base64") {
+                    bail!("base64 layer data is not supported, save the map with CSV encoding");
+                }
+
+                match layer["chunks"].as_array() {
+                    Some(chunks) => {
+                        for chunk in chunks {
+                            json_cells(chunk, document)?;
+                        }
+                    }
+
+                    None => json_cells(layer, document)?,
+                }
+            }
+
+            Some("objectgroup") => {
+                for value in layer["objects"].as_array().into_iter().flatten() {
+                    let class =
+                        value["class"].as_str()
+                            .or(value["type"].as_str())
+                            .filter(|class| !class.is_empty())
+                            .or(value["name"].as_str())
+                            .unwrap_or_default();
+
+                    let number = |name: &str| value[name].as_f64().unwrap_or(0.0);
+
+                    document.objects.push(object(
+                        class.to_string(),
+                        number("x"),
+                        number("y"),
+                        number("width"),
+                        number("height"),
+                        value["gid"].is_u64(),
+                    ));
+                }
+            }
+
+            Some("group") => json_layers(&layer["layers"], document)?,
+
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn json_cells(value: &Value, document: &mut Document) -> Result<()> {
+    let mut data = Data {
+        width:  value["width"].as_i64().context("tile layer has no width")? as i32,
+        origin: Point2::new(
+            value["x"].as_i64().unwrap_or(0) as i32,
+            value["y"].as_i64().unwrap_or(0) as i32,
+        ),
+        index: 0,
+        shown: true,
+    };
+
+    for gid in value["data"].as_array().context("tile layer has no data")? {
+        let gid = gid.as_u64().context("invalid tile id in layer data")? as u32;
+
+        document.cell(&mut data, gid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CROSSING_TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="5" height="5" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="roads" tilewidth="16" tileheight="16" tilecount="4">
+  <tile id="0" class="straight">
+   <properties>
+    <property name="rotation" type="int" value="90"/>
+   </properties>
+  </tile>
+  <tile id="1" class="straight">
+   <properties>
+    <property name="rotation" type="int" value="180"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="roads" width="5" height="5">
+  <data encoding="csv">
+0,0,2,0,0,
+0,0,2,0,0,
+1,1,4,1,1,
+0,0,2,0,0,
+0,0,2,0,0
+</data>
+ </layer>
+ <objectgroup id="2" name="objects">
+  <object id="1" type="stoplight" x="40" y="40"/>
+  <object id="2" type="spawn" x="8" y="40"/>
+ </objectgroup>
+</map>
+"#;
+
+    const FLIPPED_TMJ: &str = r#"{
+ "orientation": "orthogonal",
+ "tilewidth": 16,
+ "tileheight": 16,
+ "tilesets": [
+  {
+   "firstgid": 1,
+   "tiles": [
+    { "id": 0, "type": "straight", "properties": [{ "name": "rotation", "type": "int", "value": 90 }] }
+   ]
+  }
+ ],
+ "layers": [
+  { "type": "tilelayer", "width": 3, "height": 1, "data": [2147483649, 2147483649, 2147483649] }
+ ]
+}"#;
+
+    const CROSSING_TMJ: &str = r#"{
+ "orientation": "orthogonal",
+ "tilewidth": 16,
+ "tileheight": 16,
+ "tilesets": [
+  {
+   "firstgid": 1,
+   "tiles": [
+    { "id": 0, "class": "straight", "properties": [{ "name": "rotation", "type": "int", "value": 90 }] },
+    { "id": 1, "class": "straight", "properties": [{ "name": "rotation", "type": "int", "value": 180 }] }
+   ]
+  }
+ ],
+ "layers": [
+  {
+   "type": "tilelayer",
+   "width": 5,
+   "height": 5,
+   "data": [0, 0, 2, 0, 0, 0, 0, 2, 0, 0, 1, 1, 4, 1, 1, 0, 0, 2, 0, 0, 0, 0, 2, 0, 0]
+  },
+  {
+   "type": "objectgroup",
+   "objects": [
+    { "id": 1, "type": "stop_sign", "x": 56, "y": 24 },
+    { "id": 2, "name": "spawn", "x": 40, "y": 8 }
+   ]
+  }
+ ]
+}"#;
+
+    fn constant(map: &TiledMap, pos: (i32, i32)) -> Option<Direction> {
+        match map.tiles.get(&Point2::new(pos.0, pos.1)).map(|tile| &tile.dir) {
+            Some(TileDirection::Constant(dir)) => Some(*dir),
+            _                                  => None,
+        }
+    }
+
+    #[test]
+    fn parses_tmx_tiles_markers_and_spawns() {
+        let map = TiledImporter::new().parse_tmx(CROSSING_TMX).unwrap();
+        let pos = Point2::new(2, 2);
+
+        assert_eq!(constant(&map, (0, 2)), Some(Direction::Straight(Cardinal::Right)));
+        assert_eq!(constant(&map, (2, 0)), Some(Direction::Straight(Cardinal::Down)));
+        assert!(matches!(map.tiles.get(&pos).map(|tile| &tile.dir), Some(TileDirection::Intersection(_))));
+
+        assert_eq!(map.markers, HashMap::from([(pos, Marker::Stoplight)]));
+        assert_eq!(map.spawns, vec![Point2::new(0.0, 2.0 * TILE_SIZE_F)]);
+
+        let grid = map.grid().unwrap();
+
+        assert_eq!(grid.controls.len(), 1);
+        assert_eq!(grid.entries, vec![Point2::new(0.0, 2.0 * TILE_SIZE_F)]);
+    }
+
+    #[test]
+    fn parses_tmj_tiles_markers_and_spawns() {
+        let map = TiledImporter::new().parse_tmj(CROSSING_TMJ).unwrap();
+
+        assert_eq!(constant(&map, (4, 2)), Some(Direction::Straight(Cardinal::Right)));
+        assert_eq!(constant(&map, (2, 4)), Some(Direction::Straight(Cardinal::Down)));
+
+        assert_eq!(map.markers, HashMap::from([(Point2::new(2, 2), Marker::StopSign)]));
+        assert_eq!(map.spawns, vec![Point2::new(2.0 * TILE_SIZE_F, 0.0)]);
+    }
+
+    #[test]
+    fn applies_flip_flags() {
+        let map = TiledImporter::new().parse_tmj(FLIPPED_TMJ).unwrap();
+
+        for x in 0..3 {
+            assert_eq!(constant(&map, (x, 0)), Some(Direction::Straight(Cardinal::Left)));
+        }
+    }
+
+    #[test]
+    fn keeps_road_entries_without_spawns() {
+        let map = TiledImporter::new().parse_tmj(FLIPPED_TMJ).unwrap();
+
+        assert!(map.spawns.is_empty());
+        assert!(map.markers.is_empty());
+
+        let grid = map.grid().unwrap();
+
+        assert_eq!(grid.entries, vec![Point2::new(2.0 * TILE_SIZE_F, 0.0)]);
+        assert_eq!(grid.exits, vec![Point2::new(0, 0)]);
+    }
+
+    #[test]
+    fn rejects_markers_away_from_intersections() {
+        let json = CROSSING_TMJ.replace(r#""x": 56, "y": 24"#, r#""x": 8, "y": 8"#);
+
+        assert!(TiledImporter::new().parse_tmj(&json).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::bounds::Bounds;
+use crate::direction::Cardinal;
+use crate::intersection::Intersection;
+use crate::priority_sign::Priority;
+use crate::segment_bounds::SegmentBounds;
+use crate::stoplight::Signal;
+use crate::vehicle::Vehicle;
+
+use parry2d::na::Point2;
+
+#[derive(Debug, Clone)]
+pub struct SignalHead {
+    pub dir:    Cardinal,
+    pub line:   SegmentBounds,
+    pub signal: Signal,
+}
+
+pub enum ControlState {
+    StopSign {
+        center: Point2<f32>,
+        lines:  Vec<SegmentBounds>,
+    },
+    PrioritySign {
+        priority: Priority,
+        lines:    Vec<SegmentBounds>,
+    },
+    Stoplight {
+        heads:     Vec<SignalHead>,
+        phase:     usize,
+        remaining: f32,
+        clearance: f32,
+        held:      bool,
+    },
+}
+
+pub trait TrafficControl {
+    fn update(&mut self, time: u32, vehicles: &[Vehicle]);
+
+    fn blocks(&self, vehicle: &Vehicle, collider: &dyn Bounds) -> bool;
+
+    fn state(&self) -> ControlState;
+
+    fn intersection(&self) -> &Intersection;
+
+    fn advance_phase(&mut self) {}
+
+    fn hold_phase(&mut self, _hold: bool) {}
+}
@@ -1,39 +1,68 @@
 use crate::bounds::Bounds;
 use crate::rect_bounds::RectBounds;
-use crate::direction::{TileDirection, Direction};
-use crate::stop_sign::StopSign;
-use crate::stoplight::Stoplight;
-use crate::tile::Tile;
-use crate::tile_map::{TileMap, TILE_SIZE_F};
+use crate::direction::{TileDirection, Direction, Cardinal};
+use crate::grid::Grid;
+use crate::lane::{self, Side};
+use crate::mobil;
+use crate::tile_map::{self, TILE_SIZE_F};
+use crate::traffic_control::TrafficControl;
 
-use parry2d::na::Point2;
+use parry2d::na::{Point2, Vector2};
 use anyhow::{Result, Context};
 
+use std::sync::atomic::{AtomicU32, Ordering};
+
 pub const ACCEL: f32 = 0.25;
 pub const BRAKE: f32 = -0.5;
 pub const MAX_SPEED: f32 = 20.0;
 
+pub const LANE_CHANGE_SPEED: f32 = 7.5;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Brake {
+    Vehicle(u32),
+    Control(usize),
+}
+
 #[derive(Clone, Copy)]
 pub struct Vehicle {
+    pub id: u32,
+
     pub pos:      Point2<f32>,
     pub tile_pos: Point2<i32>,
 
     length: f32,
     speed:  f32,
 
-    pub dir: Direction,
+    pub dir:    Direction,
+    pub next:   Option<Direction>,
+    pub entry:  Option<Cardinal>,
+    pub intent: Option<Cardinal>,
+
+    pub lateral: f32,
+    pub brake:   Option<Brake>,
 }
 
 impl Vehicle {
-    pub fn new(pos: Point2<f32>, length: f32, speed: f32, tiles: &TileMap<Tile>) -> Self {
-        let tile = tiles.at_pos(&pos).expect("vehicle not on tile");
+    pub fn new(pos: Point2<f32>, length: f32, speed: f32, grid: &Grid) -> Self {
+        let tile = grid.tiles.at_pos(&pos).expect("vehicle not on tile");
 
         if let TileDirection::Constant(dir) = tile.dir {
+            let intent = lane::pick_intent(grid, tile.pos, dir);
+
             Self {
+                id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
                 pos,
                 length,
                 speed,
                 dir,
+                next:     Self::plan(grid, tile.pos, dir, intent),
+                entry:    None,
+                intent,
+                lateral:  0.0,
+                brake:    None,
                 tile_pos: tile.pos,
             }
         } else {
@@ -41,22 +70,88 @@ impl Vehicle {
         }
     }
 
+    fn plan(
+        grid:     &Grid,
+        tile_pos: Point2<i32>,
+        dir:      Direction,
+        intent:   Option<Cardinal>,
+    ) -> Option<Direction> {
+        let next_pos  = tile_pos + dir.out_dir().offset();
+        let next_tile = grid.tiles.get(&next_pos)?;
+
+        let heading =
+            match grid.tiles.get(&tile_pos).map(|tile| &tile.dir) {
+                Some(TileDirection::Intersection(_)) => dir,
+                _                                    => Direction::Straight(dir.out_dir()),
+            };
+
+        match next_tile.dir {
+            TileDirection::Constant(_)     => None,
+            TileDirection::Intersection(_) => Some(Self::route(grid, next_pos, heading, intent)),
+        }
+    }
+
+    fn route(
+        grid:   &Grid,
+        pos:    Point2<i32>,
+        dir:    Direction,
+        intent: Option<Cardinal>,
+    ) -> Direction {
+        let Some(tile) = grid.tiles.get(&pos) else {
+            return dir;
+        };
+
+        tile.dir.choose(dir, |out_dir| {
+            intent.is_none_or(|intent| {
+                lane::reaches(grid, pos + out_dir.offset(), dir.towards(out_dir), intent)
+            })
+        })
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    fn side(&self) -> Vector2<f32> {
+        Side::Right.cardinal(self.dir.out_dir()).vector()
+    }
+
+    pub fn center(&self) -> Point2<f32> {
+        self.pos + self.side() * self.lateral
+    }
+
+    pub fn turning_left(&self) -> bool {
+        match self.intent {
+            Some(intent) => intent == self.dir.out_dir().counter_rotate(),
+            None         => self.next.is_some_and(|next| next.is_left_turn()),
+        }
+    }
+
     pub fn bounds(&self) -> RectBounds {
-        RectBounds::vehicle(&self.pos, self.length, self.dir)
+        RectBounds::vehicle(&self.center(), self.length, self.dir)
     }
 
-    pub fn update(
-        &mut self,
-        vehicles:   &[&Vehicle],
-        tiles:      &TileMap<Tile>,
-        stop_signs: &[StopSign],
-        stoplights: &[Stoplight],
-    ) -> Result<()> {
-        let cur_tile = tiles.at_pos(&self.pos).context("")?;
+    pub fn collider(&self) -> RectBounds {
+        RectBounds::collider(&self.center(), self.length, self.speed, self.dir)
+    }
+
+    pub fn update(&mut self, vehicles: &[&Vehicle], grid: &Grid) -> Result<()> {
+        let cur_tile =
+            grid.tiles
+                .get(&self.tile_pos)
+                .filter(|tile| tile_map::contains(&tile.pos, &self.pos))
+                .or_else(|| grid.tiles.at_pos(&self.pos))
+                .context("")?;
 
         let tile_dir =
             if cur_tile.pos != self.tile_pos {
-                cur_tile.dir.as_dir(self.dir)
+                self.next
+                    .take()
+                    .unwrap_or_else(|| Self::route(grid, cur_tile.pos, self.dir, self.intent))
             } else {
                 self.dir
             };
@@ -68,12 +163,40 @@ impl Vehicle {
                 tile_pos + (-tile_dir.in_dir().vector() / 2.0)
             ) * TILE_SIZE_F;
 
+            let left = self.entry.is_some();
+
+            self.entry =
+                match cur_tile.dir {
+                    TileDirection::Constant(_)     => None,
+                    TileDirection::Intersection(_) => self.entry.or(Some(self.dir.out_dir())),
+                };
+
+            if left && self.entry.is_none() {
+                self.intent = lane::pick_intent(grid, cur_tile.pos, tile_dir);
+            }
+
+            if !matches!(tile_dir, Direction::Straight(_)) || self.entry.is_some() {
+                self.lateral = 0.0;
+            }
+
             self.tile_pos = cur_tile.pos;
-            self.dir = tile_dir;
+            self.dir  = tile_dir;
+            self.next = Self::plan(grid, cur_tile.pos, tile_dir, self.intent);
         }
 
+        if self.lateral != 0.0 {
+            let step = LANE_CHANGE_SPEED / 60.0;
+            self.lateral -= self.lateral.clamp(-step, step);
+        } else if self.next.is_none() {
+            if let Some(side) = mobil::decide(self, vehicles, grid) {
+                self.change_lane(grid, side);
+            }
+        }
+
+        self.brake = self.brake_reason(vehicles, &grid.controls);
+
         self.speed +=
-            if self.should_slow(vehicles, stop_signs, stoplights) {
+            if self.brake.is_some() {
                 BRAKE
             } else {
                 ACCEL
@@ -85,50 +208,50 @@ impl Vehicle {
         Ok(())
     }
 
+    fn change_lane(&mut self, grid: &Grid, side: Side) {
+        let heading = self.dir.out_dir();
+
+        let Some(target) = lane::neighbour(&grid.tiles, self.tile_pos, heading, side) else {
+            return;
+        };
+
+        let offset = side.cardinal(heading).vector() * TILE_SIZE_F;
+
+        self.pos     += offset;
+        self.lateral -= offset.dot(&self.side());
+
+        self.tile_pos = target;
+        self.next     = Self::plan(grid, target, self.dir, self.intent);
+    }
+
     pub fn should_slow(
         &self,
-        vehicles:   &[&Vehicle],
-        stop_signs: &[StopSign],
-        stoplights: &[Stoplight],
+        vehicles: &[&Vehicle],
+        controls: &[Box<dyn TrafficControl>],
     ) -> bool {
-        let collider =
-            RectBounds::collider(
-                &self.pos,
-                self.length,
-                self.speed,
-                self.dir,
-            );
-
-        let collisions =
-            vehicles.iter().any(|vehicle| {
+        self.brake_reason(vehicles, controls).is_some()
+    }
+
+    pub fn brake_reason(
+        &self,
+        vehicles: &[&Vehicle],
+        controls: &[Box<dyn TrafficControl>],
+    ) -> Option<Brake> {
+        let collider = self.collider();
+
+        let collision =
+            vehicles.iter().find(|vehicle| {
                 let bounds = vehicle.bounds();
                 collider.colliding(&bounds)
             });
 
-        let stop_signs =
-            stop_signs.iter().any(|stop_sign| {
-                stop_sign.colliding(self.tile_pos, &collider)
-            });
-
-        let stoplights =
-            stoplights.iter().any(|stoplight| {
-                let on_axis =
-                    self.dir.out_dir().on_axis(&stoplight.axis);
-
-                let bounds = stoplight.bounds();
-
-                if stoplight.grace_period() {
-                    bounds.colliding(&collider)
-                } else {
-                    let axis_bounds = bounds.opp_axis(stoplight.axis);
-
-                    !on_axis && (
-                        collider.colliding(axis_bounds.0) ||
-                        collider.colliding(axis_bounds.1)
-                    )
-                }
-            });
+        if let Some(vehicle) = collision {
+            return Some(Brake::Vehicle(vehicle.id));
+        }
 
-        collisions || stop_signs || stoplights
+        controls
+            .iter()
+            .position(|control| control.blocks(self, &collider))
+            .map(Brake::Control)
     }
 }
@@ -0,0 +1,15 @@
+use quick_xml::events::BytesStart;
+use quick_xml::XmlVersion;
+use anyhow::{Context, Result};
+
+pub fn attr(element: &BytesStart, name: &str) -> Result<String> {
+    optional_attr(element, name)?.with_context(|| format!("missing {name} attribute"))
+}
+
+pub fn optional_attr(element: &BytesStart, name: &str) -> Result<Option<String>> {
+    let Some(attr) = element.try_get_attribute(name)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(attr.normalized_value(XmlVersion::Implicit1_0)?.into_owned()))
+}
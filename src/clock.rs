@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+pub const MIN_SPEED:   f32      = 0.25;
+pub const MAX_SPEED:   f32      = 16.0;
+pub const RATE_WINDOW: Duration = Duration::from_millis(500);
+
+pub struct Clock {
+    pub paused:    bool,
+    pub speed:     f32,
+    pub tick_rate: f32,
+
+    budget: f32,
+    steps:  u32,
+    since:  Instant,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            paused:    false,
+            speed:     1.0,
+            tick_rate: 0.0,
+            budget:    0.0,
+            steps:     0,
+            since:     Instant::now(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    pub fn advance(&mut self, steps_per_frame: f32) -> u32 {
+        if self.paused {
+            self.budget = 0.0;
+            return 0;
+        }
+
+        self.budget += self.speed * steps_per_frame;
+
+        let steps    = self.budget.floor();
+        self.budget -= steps;
+
+        steps as u32
+    }
+
+    pub fn record(&mut self, steps: u32) {
+        self.steps += steps;
+
+        let elapsed = self.since.elapsed();
+
+        if elapsed >= RATE_WINDOW {
+            self.tick_rate = self.steps as f32 / elapsed.as_secs_f32();
+            self.steps     = 0;
+            self.since     = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use routing::direction::Cardinal;
+use routing::editor::{Editor, Issue, Tool};
+use routing::grid::Grid;
+use routing::tile_map::TILE_SIZE_F;
+
+use renderer::renderer::Renderer;
+use renderer::scene::Overlay;
+
+use nalgebra::Point2;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+use anyhow::Result;
+
+use std::path::PathBuf;
+
+pub enum Outcome {
+    Ignored,
+    Changed,
+    Play(Grid),
+}
+
+pub struct EditMode {
+    pub editor:  Editor,
+    pub active:  bool,
+    pub path:    PathBuf,
+    pub issues:  Vec<Issue>,
+    pub preview: Grid,
+
+    cursor:    Point2<f32>,
+    modifiers: ModifiersState,
+    painting:  bool,
+}
+
+impl EditMode {
+    pub fn new(editor: Editor, path: PathBuf) -> Result<Self> {
+        let preview = editor.grid()?;
+
+        Ok(Self {
+            editor,
+            active:    false,
+            path,
+            issues:    Vec::new(),
+            preview,
+            cursor:    Point2::origin(),
+            modifiers: ModifiersState::empty(),
+            painting:  false,
+        })
+    }
+
+    pub fn tile(&self) -> Point2<i32> {
+        Point2::new(
+            (self.cursor.x / TILE_SIZE_F).round() as i32,
+            (self.cursor.y / TILE_SIZE_F).round() as i32,
+        )
+    }
+
+    pub fn overlay(&self) -> Overlay<'_> {
+        Overlay {
+            issues:  &self.issues,
+            markers: self.editor.markers(),
+            cursor:  Some(self.tile()),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        if self.active {
+            format!(
+                "Traffic - editing {} - {:?} facing {:?} - {} issues",
+                self.path.display(),
+                self.editor.tool,
+                self.editor.rotation,
+                self.issues.len(),
+            )
+        } else {
+            String::from("Traffic")
+        }
+    }
+
+    pub fn handle(&mut self, event: &WindowEvent, renderer: &Renderer) -> Result<Outcome> {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+
+                Ok(Outcome::Ignored)
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                self.key(&event.logical_key)
+            }
+
+            WindowEvent::CursorMoved { position, .. } => {
+                let last = self.tile();
+
+                self.cursor = renderer.world(position.x, position.y);
+
+                if !self.active || self.tile() == last {
+                    return Ok(Outcome::Ignored);
+                }
+
+                if self.painting {
+                    self.editor.drag(self.tile());
+                    return self.refresh();
+                }
+
+                Ok(Outcome::Changed)
+            }
+
+            WindowEvent::MouseInput { state, button, .. } if self.active => {
+                self.click(*state, *button)
+            }
+
+            _ => Ok(Outcome::Ignored),
+        }
+    }
+
+    fn key(&mut self, key: &Key) -> Result<Outcome> {
+        let ctrl  = self.modifiers.control_key() || self.modifiers.super_key();
+        let shift = self.modifiers.shift_key();
+
+        let name =
+            match key {
+                Key::Character(ch)              => ch.to_lowercase(),
+                Key::Named(NamedKey::Tab)       => String::from("e"),
+                Key::Named(NamedKey::Delete)    => String::from("x"),
+                Key::Named(NamedKey::Backspace) => String::from("x"),
+                _                               => return Ok(Outcome::Ignored),
+            };
+
+        if name == "e" && !ctrl {
+            return self.toggle();
+        }
+
+        if !self.active {
+            return Ok(Outcome::Ignored);
+        }
+
+        let tool =
+            match (name.as_str(), ctrl) {
+                ("1", false) => Tool::Straight,
+                ("2", false) => Tool::Turn,
+                ("3", false) => Tool::LeftTurn,
+                ("4", false) => Tool::Intersection,
+                ("5", false) => Tool::StopSign,
+                ("6", false) => Tool::Stoplight,
+                ("7", false) => Tool::TurnTable,
+                ("x", false) => Tool::Erase,
+
+                ("r", false) => {
+                    self.editor.rotation = self.editor.rotation.rotate();
+                    return Ok(Outcome::Changed);
+                }
+
+                ("z", true) if shift => return self.history(Editor::redo),
+                ("z", true)          => return self.history(Editor::undo),
+                ("y", true)          => return self.history(Editor::redo),
+
+                ("s", true) => {
+                    self.editor.save(&self.path)?;
+                    return Ok(Outcome::Changed);
+                }
+
+                _ => return Ok(Outcome::Ignored),
+            };
+
+        self.editor.tool = tool;
+
+        Ok(Outcome::Changed)
+    }
+
+    fn click(&mut self, state: ElementState, button: MouseButton) -> Result<Outcome> {
+        let pos = self.tile();
+
+        match (button, state) {
+            (MouseButton::Left, ElementState::Pressed) if self.editor.tool == Tool::TurnTable => {
+                let out = self.edge();
+                self.editor.toggle_turn(pos, self.editor.rotation, out)?;
+            }
+
+            (MouseButton::Left, ElementState::Pressed) => {
+                self.painting = true;
+                self.editor.begin(pos);
+            }
+
+            (MouseButton::Left, ElementState::Released) => {
+                self.painting = false;
+                self.editor.end();
+            }
+
+            (MouseButton::Right, ElementState::Pressed) => {
+                self.editor.rotate_at(pos);
+            }
+
+            _ => return Ok(Outcome::Ignored),
+        }
+
+        self.refresh()
+    }
+
+    fn toggle(&mut self) -> Result<Outcome> {
+        if !self.active {
+            self.active = true;
+            return self.refresh();
+        }
+
+        self.painting = false;
+        self.editor.end();
+
+        let grid = self.editor.grid()?;
+
+        self.active = false;
+
+        Ok(Outcome::Play(grid))
+    }
+
+    fn history(&mut self, step: fn(&mut Editor) -> bool) -> Result<Outcome> {
+        if step(&mut self.editor) {
+            self.refresh()
+        } else {
+            Ok(Outcome::Ignored)
+        }
+    }
+
+    fn refresh(&mut self) -> Result<Outcome> {
+        self.issues = self.editor.validate()?;
+
+        self.preview =
+            match self.editor.grid() {
+                Ok(grid) => grid,
+                Err(_)   => Grid::new(self.editor.tiles()?, Vec::new()),
+            };
+
+        Ok(Outcome::Changed)
+    }
+
+    fn edge(&self) -> Cardinal {
+        let tile = self.tile();
+
+        let x = self.cursor.x / TILE_SIZE_F - tile.x as f32;
+        let y = self.cursor.y / TILE_SIZE_F - tile.y as f32;
+
+        if x.abs() > y.abs() {
+            if x > 0.0 { Cardinal::Right } else { Cardinal::Left }
+        } else if y > 0.0 {
+            Cardinal::Down
+        } else {
+            Cardinal::Up
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::clock::Clock;
+use crate::simulation::Simulation;
+use crate::STEPS_PER_SECOND;
+
+use routing::stoplight::Signal;
+use routing::tile_map::TILE_SIZE_F;
+use routing::traffic_control::ControlState;
+
+pub fn lines(simulation: &Simulation, clock: &Clock) -> Vec<String> {
+    let state = if clock.paused { "paused" } else { "running" };
+
+    let mut lines = vec![
+        format!("{state} at {}x, seed {}", clock.speed, simulation.seed),
+        format!("time {:.1}s, {:.0} ticks/s", simulation.time as f32 / STEPS_PER_SECOND, clock.tick_rate),
+        format!("{} vehicles, avg speed {:.2} tiles/s", simulation.vehicles.len(), simulation.average_speed() / TILE_SIZE_F),
+        format!("throughput {:.1} vehicles/min, {} exited", simulation.throughput(), simulation.exited),
+    ];
+
+    for (idx, control) in simulation.grid.controls.iter().enumerate() {
+        let ControlState::Stoplight { heads, phase, remaining, clearance, held } = control.state() else {
+            continue;
+        };
+
+        let serving =
+            heads
+                .iter()
+                .filter(|head| head.signal != Signal::Red)
+                .map(|head| format!("{:?}", head.dir))
+                .collect::<Vec<_>>();
+
+        let serving =
+            if serving.is_empty() {
+                String::from("all red")
+            } else {
+                serving.join("/")
+            };
+
+        let timing =
+            if held {
+                String::from("held")
+            } else if remaining > 0.0 {
+                format!("{remaining:.1}s green")
+            } else {
+                format!("{clearance:.1}s clearance")
+            };
+
+        lines.push(format!("stoplight {idx}: phase {} ({serving}), {timing}", phase + 1));
+    }
+
+    lines.push(String::from("space pause, . step, +/- speed, n new seed, h hide"));
+    lines.push(String::from("click to inspect, enter next phase, l hold phase, t cycle control"));
+
+    lines
+}
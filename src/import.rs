@@ -0,0 +1,73 @@
+use crate::options::{Import, Options};
+
+use routing::ascii_map::Marker;
+use routing::editor::Editor;
+use routing::grid::{Grid, GridGenerator};
+use routing::osm::OsmImporter;
+use routing::sumo::{Demand, SumoImporter};
+use routing::tiled::TiledImporter;
+use routing::traffic_control::ControlState;
+
+use anyhow::Result;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub fn load(import: &Import, options: &Options) -> Result<(Grid, Vec<Demand>)> {
+    match import {
+        Import::Generate(cols, rows) => {
+            let mut generator = GridGenerator::new(*cols, *rows);
+            generator.streets = options.streets;
+
+            if let Some(policy) = options.policy {
+                generator.policy = policy;
+            }
+
+            Ok((generator.generate()?, Vec::new()))
+        }
+
+        Import::Osm(path) => Ok((OsmImporter::new().import(path)?, Vec::new())),
+
+        Import::Sumo(path) => {
+            let net = SumoImporter::new().import(path)?;
+
+            let demand =
+                match &options.demand {
+                    Some(routes) => net.import_routes(routes)?,
+                    None         => Vec::new(),
+                };
+
+            Ok((net.grid, demand))
+        }
+
+        Import::Tiled(path) => Ok((TiledImporter::new().import(path)?.grid()?, Vec::new())),
+    }
+}
+
+pub fn path(import: &Import) -> PathBuf {
+    match import {
+        Import::Generate(cols, rows) => PathBuf::from(format!("grid-{cols}x{rows}.txt")),
+        Import::Osm(path)            => path.with_extension("txt"),
+        Import::Sumo(path)           => path.with_extension("txt"),
+        Import::Tiled(path)          => path.with_extension("txt"),
+    }
+}
+
+pub fn editor(grid: &Grid) -> Editor {
+    let markers =
+        grid.controls
+            .iter()
+            .filter_map(|control| {
+                let marker =
+                    match control.state() {
+                        ControlState::StopSign { .. }     => Marker::StopSign,
+                        ControlState::Stoplight { .. }    => Marker::Stoplight,
+                        ControlState::PrioritySign { .. } => return None,
+                    };
+
+                Some((*control.intersection().tiles.first()?, marker))
+            })
+            .collect::<HashMap<_, _>>();
+
+    Editor::from_tiles(&grid.tiles, &markers)
+}
@@ -0,0 +1,178 @@
+use crate::simulation::Simulation;
+
+use routing::bounds::Bounds;
+use routing::direction::TileDirection;
+use routing::intersection::Intersection;
+use routing::priority_sign::Priority;
+use routing::tile_map::TILE_SIZE_F;
+use routing::traffic_control::{ControlState, TrafficControl};
+use routing::vehicle::Brake;
+
+use nalgebra::Point2;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Selection {
+    Vehicle(u32),
+    Junction(Point2<i32>),
+}
+
+impl Selection {
+    pub fn junction(self) -> Option<Point2<i32>> {
+        match self {
+            Self::Junction(pos) => Some(pos),
+            Self::Vehicle(_)    => None,
+        }
+    }
+}
+
+pub fn pick(simulation: &Simulation, pos: Point2<f32>) -> Option<Selection> {
+    let vehicle =
+        simulation.vehicles
+            .iter()
+            .find(|vehicle| vehicle.bounds().contains(&pos));
+
+    if let Some(vehicle) = vehicle {
+        return Some(Selection::Vehicle(vehicle.id));
+    }
+
+    let tile = simulation.grid.tiles.at_pos(&pos)?;
+
+    matches!(tile.dir, TileDirection::Intersection(_)).then_some(Selection::Junction(tile.pos))
+}
+
+pub fn lines(simulation: &Simulation, selection: Selection) -> Vec<String> {
+    match selection {
+        Selection::Vehicle(id)   => vehicle(simulation, id),
+        Selection::Junction(pos) => junction(simulation, pos),
+    }
+}
+
+fn vehicle(simulation: &Simulation, id: u32) -> Vec<String> {
+    let Some(vehicle) = simulation.vehicles.iter().find(|vehicle| vehicle.id == id) else {
+        return Vec::new();
+    };
+
+    let next =
+        match vehicle.next {
+            Some(next) => format!("{next:?}"),
+            None       => String::from("-"),
+        };
+
+    let exit =
+        match vehicle.intent {
+            Some(intent) => format!("{intent:?}"),
+            None         => String::from("-"),
+        };
+
+    let braking =
+        match vehicle.brake {
+            None => String::from("no"),
+
+            Some(Brake::Vehicle(other)) => format!("for vehicle {other}"),
+
+            Some(Brake::Control(idx)) => {
+                let kind =
+                    simulation.grid.controls
+                        .get(idx)
+                        .map(|control| kind(control.as_ref()))
+                        .unwrap_or("control");
+
+                format!("for {kind} {idx}")
+            }
+        };
+
+    vec![
+        format!("vehicle {}", vehicle.id),
+        format!("speed {:.2} tiles/s", vehicle.speed() / TILE_SIZE_F),
+        format!("direction {:?}", vehicle.dir),
+        format!("tile ({}, {})", vehicle.tile_pos.x, vehicle.tile_pos.y),
+        format!("route next {next}, exit {exit}"),
+        format!("braking {braking}"),
+    ]
+}
+
+fn junction(simulation: &Simulation, pos: Point2<i32>) -> Vec<String> {
+    let vehicles = &simulation.vehicles;
+
+    let control =
+        simulation.grid.controls
+            .iter()
+            .enumerate()
+            .find(|(_, control)| control.intersection().contains(pos));
+
+    let Some((idx, control)) = control else {
+        let Ok(intersection) = Intersection::new(&simulation.grid.tiles, pos) else {
+            return Vec::new();
+        };
+
+        let mut lines = vec![String::from("uncontrolled intersection")];
+
+        for approach in &intersection.approaches {
+            lines.push(format!(
+                "{:?} at ({}, {}): queue {}",
+                approach.dir, approach.entry.x, approach.entry.y, approach.queue(vehicles),
+            ));
+        }
+
+        return lines;
+    };
+
+    let intersection = control.intersection();
+
+    let inside =
+        vehicles
+            .iter()
+            .filter(|vehicle| intersection.contains(vehicle.tile_pos))
+            .count();
+
+    let mut lines = vec![
+        format!("{} {idx}", kind(control.as_ref())),
+        format!("{} tiles, {inside} vehicles inside", intersection.tiles.len()),
+    ];
+
+    match control.state() {
+        ControlState::Stoplight { heads, phase, remaining, clearance, held } => {
+            if held {
+                lines.push(format!("phase {} held", phase + 1));
+            } else if remaining > 0.0 {
+                lines.push(format!("phase {}, {remaining:.1}s green", phase + 1));
+            } else {
+                lines.push(format!("phase {}, {clearance:.1}s clearance", phase + 1));
+            }
+
+            for (head, approach) in heads.iter().zip(&intersection.approaches) {
+                lines.push(format!(
+                    "{:?} at ({}, {}): {:?}, queue {}",
+                    head.dir, approach.entry.x, approach.entry.y, head.signal, approach.queue(vehicles),
+                ));
+            }
+        }
+
+        ControlState::StopSign { .. } | ControlState::PrioritySign { .. } => {
+            for approach in &intersection.approaches {
+                let waiting =
+                    approach
+                        .waiting(vehicles)
+                        .filter(|vehicle| vehicle.speed() == 0.0)
+                        .map_or(String::new(), |vehicle| format!(", vehicle {} stopped", vehicle.id));
+
+                lines.push(format!(
+                    "{:?} at ({}, {}): queue {}{waiting}",
+                    approach.dir, approach.entry.x, approach.entry.y, approach.queue(vehicles),
+                ));
+            }
+        }
+    }
+
+    lines
+}
+
+fn kind(control: &dyn TrafficControl) -> &'static str {
+    match control.state() {
+        ControlState::Stoplight { .. } => "stoplight",
+        ControlState::StopSign { .. }  => "stop sign",
+
+        ControlState::PrioritySign { priority: Priority::Stop, .. }  => "priority stop sign",
+        ControlState::PrioritySign { priority: Priority::Yield, .. } => "yield sign",
+    }
+}
@@ -1,23 +1,49 @@
+mod clock;
+mod editing;
+mod hud;
+mod import;
+mod inspect;
+mod navigation;
+mod options;
+mod simulation;
+
+use crate::clock::Clock;
+use crate::editing::{EditMode, Outcome};
+use crate::inspect::Selection;
+use crate::navigation::{Command, Navigation};
+use crate::options::{FrontendKind, Options};
+use crate::simulation::Simulation;
+
+use routing::ascii_map::Marker;
 use routing::direction::{Cardinal, Direction, TileDirection};
-use routing::stop_sign::StopSign;
-use routing::stoplight::Stoplight;
+use routing::editor::Editor;
+use routing::traffic_control::ControlState;
 use routing::tile::Tile;
 use routing::tile_map::{TileMap, TILE_SIZE_F};
-use routing::vehicle::Vehicle;
+use routing::vehicle;
 
+use renderer::export::Exporter;
+use renderer::frontend::{Frontend, World};
+use renderer::headless::{FrameDump, Headless};
 use renderer::renderer::Renderer;
+use renderer::scene::Overlay;
+use renderer::svg::Svg;
+use renderer::terminal::{Input, Terminal};
+use renderer::texture_loader::TextureLoader;
 
 use routing::direction::Cardinal::*;
 use routing::direction::Direction::*;
 
-use rand::seq::SliceRandom;
-use rand::Rng;
-
 use nalgebra::Point2;
 use winit::event::{Event, WindowEvent};
 use anyhow::Result;
 
 use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const STEPS_PER_SECOND: f32 = 60.0;
+pub const TERMINAL_FPS:     f32 = 30.0;
 
 macro_rules! intersection {
     ( $( $key:expr => $val:expr ),* $( , )? ) => {{
@@ -51,13 +77,338 @@ fn intersection(dirs: HashMap<Direction, Vec<Cardinal>>) -> TileDirection {
     TileDirection::Intersection(dirs)
 }
 
+fn offscreen(mut simulation: Simulation, editor: &Editor, options: &Options) -> Result<()> {
+    let mut dump   = options.headless.as_ref().map(FrameDump::new).transpose()?;
+    let mut export = options.export.as_ref().map(Exporter::new).transpose()?;
+
+    if let Some(export) = &mut export {
+        export.fps = options.fps;
+
+        if let Some(ffmpeg) = &options.ffmpeg {
+            export.ffmpeg = ffmpeg.clone();
+        }
+    }
+
+    let mut headless =
+        if dump.is_some() || export.is_some() {
+            let mut headless = Headless::new(options.width, options.height)?;
+
+            if let Some(dir) = &options.assets {
+                headless.loader = TextureLoader::open(dir)?;
+            }
+
+            frame(&mut headless, &simulation, options);
+            Some(headless)
+        } else {
+            None
+        };
+
+    let issues =
+        if options.overlay {
+            editor.validate()?
+        } else {
+            Vec::new()
+        };
+
+    let overlay = Overlay {
+        issues:  &issues,
+        markers: editor.markers(),
+        cursor:  None,
+    };
+
+    for _ in 0..options.frames {
+        simulation.step()?;
+
+        let Some(headless) = &mut headless else {
+            continue;
+        };
+
+        if simulation.time % options.every.max(1) != 0 {
+            continue;
+        }
+
+        let mut world = simulation.world(options.overlay.then_some(&overlay));
+        world.debug   = options.debug;
+
+        let frame = headless.render(&world)?;
+
+        if let Some(dump) = &mut dump {
+            dump.write(&frame)?;
+        }
+
+        if let Some(export) = &mut export {
+            export.push(&frame)?;
+        }
+    }
+
+    if let (Some(dump), Some(dir)) = (&dump, &options.headless) {
+        println!("wrote {} frames to {}", dump.count(), dir.display());
+    }
+
+    if let Some(export) = export {
+        let frames = export.frames();
+        let path   = export.path.clone();
+
+        export.finish()?;
+
+        println!("exported {frames} frames to {}", path.display());
+    }
+
+    if let Some(path) = &options.svg {
+        let mut svg = Svg::new(options.width, options.height);
+
+        frame(&mut svg, &simulation, options);
+
+        if options.speed {
+            svg.speed_scale = Some(vehicle::MAX_SPEED);
+        }
+
+        svg.save(path, &simulation.world(options.overlay.then_some(&overlay)))?;
+
+        println!("wrote snapshot at step {} to {}", simulation.time, path.display());
+    }
+
+    simulation.finish()
+}
+
+fn frame(frontend: &mut impl Frontend, simulation: &Simulation, options: &Options) {
+    match options.region {
+        Some((min, max)) => frontend.camera().fit_region(min, max),
+        None             => frontend.camera().fit(&simulation.grid.tiles),
+    }
+}
+
+fn window(mut simulation: Simulation, editor: Editor, options: Options) -> Result<()> {
+    let mut edit = EditMode::new(editor, options.map.clone())?;
+
+    let (mut renderer, event_loop) = Renderer::new()?;
+
+    if let Some(dir) = &options.assets {
+        renderer.loader = TextureLoader::open(dir)?;
+    }
+
+    renderer.camera().fit(&simulation.grid.tiles);
+
+    let mut navigation = Navigation::new();
+    let mut clock      = Clock::new();
+    let mut debug      = options.debug;
+    let mut selection  = None::<Selection>;
+    let mut failure    = None;
+    let mut edited     = false;
+
+    event_loop.run(|event, elwt| {
+        if let Event::WindowEvent { ref event, .. } = event {
+            match navigation.handle(event, renderer.camera(), edit.active) {
+                Some(Command::Fit)         => renderer.camera().fit(&simulation.grid.tiles),
+                Some(Command::FollowNext)  => renderer.camera().follow_next(&simulation.vehicles),
+                Some(Command::ToggleDebug) => debug = !debug,
+                Some(Command::ToggleHud)   => renderer.hud.visible = !renderer.hud.visible,
+                Some(Command::Pause)       => clock.toggle(),
+                Some(Command::Faster)      => clock.faster(),
+                Some(Command::Slower)      => clock.slower(),
+
+                Some(Command::Step) if clock.paused => {
+                    if let Err(err) = simulation.step() {
+                        eprintln!("{err:#}");
+                    }
+                }
+
+                Some(Command::Restart) => {
+                    let grid =
+                        match &options.import {
+                            Some(source) if !edited => import::load(source, &options).map(|(grid, _)| grid),
+                            _                       => edit.editor.grid(),
+                        };
+
+                    match grid {
+                        Ok(grid) => simulation.restart(grid, rand::random()),
+                        Err(err) => eprintln!("{err:#}"),
+                    }
+
+                    selection = None;
+                }
+
+                Some(Command::Select(screen)) => {
+                    selection = inspect::pick(&simulation, renderer.camera.to_world(screen));
+                }
+
+                Some(Command::AdvancePhase) => {
+                    let junction = selection.and_then(Selection::junction);
+
+                    if let Some(control) = junction.and_then(|pos| simulation.grid.control_at(pos)) {
+                        control.advance_phase();
+                    }
+                }
+
+                Some(Command::HoldPhase) => {
+                    let junction = selection.and_then(Selection::junction);
+
+                    if let Some(control) = junction.and_then(|pos| simulation.grid.control_at(pos)) {
+                        let held = matches!(control.state(), ControlState::Stoplight { held: true, .. });
+
+                        control.hold_phase(!held);
+                    }
+                }
+
+                Some(Command::CycleControl) => {
+                    if let Some(pos) = selection.and_then(Selection::junction) {
+                        if let Err(err) = simulation.grid.cycle_control(pos) {
+                            eprintln!("{err:#}");
+                        }
+                    }
+                }
+
+                Some(Command::Step) | None => {}
+            }
+
+            match edit.handle(event, &renderer) {
+                Ok(Outcome::Ignored) => {}
+
+                Ok(Outcome::Changed) => {
+                    renderer.set_title(&edit.title());
+                }
+
+                Ok(Outcome::Play(grid)) => {
+                    simulation.reset(grid);
+                    edited    = true;
+                    selection = None;
+
+                    renderer.set_title(&edit.title());
+                }
+
+                Err(err) => eprintln!("{err:#}"),
+            }
+
+            if let WindowEvent::RedrawRequested = event {
+                match renderer.loader.poll() {
+                    Ok(true)  => println!("reloaded asset pack {:?}", renderer.loader.pack.name),
+                    Ok(false) => {}
+                    Err(err)  => eprintln!("{err:#}"),
+                }
+            }
+
+            let outcome =
+                match event {
+                    WindowEvent::CloseRequested => {
+                        elwt.exit();
+                        Ok(())
+                    }
+
+                    WindowEvent::Resized(_) => renderer.update_surface(),
+
+                    WindowEvent::RedrawRequested if edit.active => {
+                        let overlay = edit.overlay();
+
+                        renderer.inspector.lines.clear();
+
+                        renderer.draw(&World {
+                            time:     simulation.time,
+                            vehicles: &[],
+                            tiles:    &edit.preview.tiles,
+                            controls: &edit.preview.controls,
+                            overlay:  Some(&overlay),
+                            debug:    false,
+                        })
+                    }
+
+                    WindowEvent::RedrawRequested => {
+                        let steps = clock.advance(1.0);
+
+                        (0..steps).try_for_each(|_| simulation.step()).and_then(|()| {
+                            clock.record(steps);
+                            renderer.hud.lines       = hud::lines(&simulation, &clock);
+                            renderer.inspector.lines =
+                                selection
+                                    .map(|selection| inspect::lines(&simulation, selection))
+                                    .unwrap_or_default();
+
+                            if renderer.inspector.lines.is_empty() {
+                                selection = None;
+                            }
+
+                            let mut world = simulation.world(None);
+                            world.debug   = debug;
+
+                            renderer.draw(&world)
+                        })
+                    }
+
+                    _ => Ok(()),
+                };
+
+            if let Err(err) = outcome {
+                failure = Some(err);
+                elwt.exit();
+            }
+        }
+    })?;
+
+    match failure {
+        Some(err) => Err(err),
+        None      => simulation.finish(),
+    }
+}
+
+fn terminal(mut simulation: Simulation) -> Result<()> {
+    let mut frontend = Terminal::new()?;
+
+    frontend.camera().fit(&simulation.grid.tiles);
+
+    let mut clock = Clock::new();
+
+    loop {
+        let tick = Instant::now();
+
+        while let Some(input) = frontend.input(Duration::ZERO)? {
+            match input {
+                Input::Quit | Input::Char('q') => return simulation.finish(),
+
+                Input::Char(' ' | 'p')                 => clock.toggle(),
+                Input::Char('s' | '.') if clock.paused => simulation.step()?,
+                Input::Char('+' | '=')                 => clock.faster(),
+                Input::Char('-')                       => clock.slower(),
+                Input::Char('f')                       => frontend.camera().fit(&simulation.grid.tiles),
+                Input::Char('v')                       => frontend.camera().follow_next(&simulation.vehicles),
+                Input::Escape                          => frontend.camera().follow = None,
+
+                Input::Arrow(dir) => {
+                    let camera = frontend.camera();
+
+                    camera.center += dir.offset().cast::<f32>() * TILE_SIZE_F;
+                    camera.follow  = None;
+                }
+
+                _ => {}
+            }
+        }
+
+        let steps = clock.advance(STEPS_PER_SECOND / TERMINAL_FPS);
+
+        for _ in 0..steps {
+            simulation.step()?;
+        }
+
+        clock.record(steps);
+
+        frontend.status = format!(
+            " {} {:>4}x | {:>7.1}s | {:>3} vehicles | space pause, s step, +/- speed, arrows pan, v follow, f fit, q quit",
+            if clock.paused { "paused " } else { "running" },
+            clock.speed,
+            simulation.time as f32 / STEPS_PER_SECOND,
+            simulation.vehicles.len(),
+        );
+
+        frontend.draw(&simulation.world(None))?;
+
+        thread::sleep(Duration::from_secs_f32(1.0 / TERMINAL_FPS).saturating_sub(tick.elapsed()));
+    }
+}
+
 fn main() -> Result<()> {
     let tiles = TileMap::new(vec![
         tile((6, -3), straight(Down)),
         tile((6, -2), straight(Down)),
         tile((6, -1), straight(Down)),
-        tile((6,  0), straight(Down)),
-        tile((6,  3), straight(Down)),
         tile((6,  4), straight(Down)),
         tile((6,  5), straight(Down)),
         tile((6,  6), straight(Down)),
@@ -66,30 +417,60 @@ fn main() -> Result<()> {
         tile((7, -3), straight(Up)),
         tile((7, -2), straight(Up)),
         tile((7, -1), straight(Up)),
-        tile((7,  0), straight(Up)),
-        tile((7,  3), straight(Up)),
         tile((7,  4), straight(Up)),
         tile((7,  5), straight(Up)),
         tile((7,  6), straight(Up)),
         tile((7,  7), straight(Up)),
 
-        tile((6, 1), intersection!(
+        tile((6, 0), intersection!(
             Straight(Left) => vec![Left],
             Straight(Down) => vec![Down, Left],
         )),
-        tile((7, 1), intersection!(
+        tile((7, 0), intersection!(
             Straight(Left) => vec![Left, Up],
             Straight(Up)   => vec![Up],
         )),
+        tile((6, 1), intersection!(
+            Straight(Left) => vec![Left],
+            Straight(Down) => vec![Down],
+        )),
+        tile((7, 1), intersection!(
+            Straight(Left) => vec![Left],
+            Straight(Up)   => vec![Up],
+        )),
         tile((6, 2), intersection!(
-            Straight(Right) => vec![Right, Down],
+            Straight(Right) => vec![Right],
             Straight(Down)  => vec![Down],
         )),
         tile((7, 2), intersection!(
+            Straight(Right) => vec![Right],
+            Straight(Up)    => vec![Up],
+        )),
+        tile((6, 3), intersection!(
+            Straight(Right) => vec![Right, Down],
+            Straight(Down)  => vec![Down],
+        )),
+        tile((7, 3), intersection!(
             Straight(Right) => vec![Right],
             Straight(Up)    => vec![Up, Right],
         )),
 
+        tile((-2, 0), straight(Left)),
+        tile((-1, 0), straight(Left)),
+        tile((0,  0), straight(Left)),
+        tile((1,  0), straight(Left)),
+        tile((2,  0), straight(Left)),
+        tile((3,  0), straight(Left)),
+        tile((4,  0), straight(Left)),
+        tile((5,  0), straight(Left)),
+        tile((8,  0), straight(Left)),
+        tile((9,  0), straight(Left)),
+        tile((10, 0), straight(Left)),
+        tile((11, 0), straight(Left)),
+        tile((12, 0), straight(Left)),
+        tile((13, 0), straight(Left)),
+        tile((14, 0), straight(Left)),
+
         tile((-2, 1), straight(Left)),
         tile((-1, 1), straight(Left)),
         tile((0,  1), straight(Left)),
@@ -121,124 +502,62 @@ fn main() -> Result<()> {
         tile((12, 2), straight(Right)),
         tile((13, 2), straight(Right)),
         tile((14, 2), straight(Right)),
-    ]);
-
-    let possible = [
-        Vehicle::new(
-            Point2::new(6.0 * TILE_SIZE_F, -3.0 * TILE_SIZE_F),
-            8.0,
-            rand::thread_rng().gen_range(2.0..10.0),
-            &tiles,
-        ),
-
-        Vehicle::new(
-            Point2::new(7.0 * TILE_SIZE_F, 7.0 * TILE_SIZE_F),
-            8.0,
-            rand::thread_rng().gen_range(2.0..10.0),
-            &tiles,
-        ),
-
-        Vehicle::new(
-            Point2::new(-2.0 * TILE_SIZE_F, 2.0 * TILE_SIZE_F),
-            8.0,
-            rand::thread_rng().gen_range(2.0..10.0),
-            &tiles,
-        ),
-
-        Vehicle::new(
-            Point2::new(14.0 * TILE_SIZE_F, 1.0 * TILE_SIZE_F),
-            8.0,
-            rand::thread_rng().gen_range(2.0..10.0),
-            &tiles,
-        ),
-    ];
-
-    let mut vehicles: Vec<Vehicle> =
-        possible
-            .choose_multiple(&mut rand::thread_rng(), 4)
-            .copied()
-            .collect();
-
-    let mut stoplights = vec![
-        Stoplight::new(Point2::new(6.5, 1.5), 10.0),
-    ];
-
-    let mut stop_signs = vec![
-        // StopSign::new(Point2::new(6.5, 1.5)),
-    ];
-
-    let (mut renderer, event_loop) = Renderer::new()?;
-
-    let mut time = 0;
 
-    let _ = event_loop.run(move |event, elwt| {
-        if let Event::WindowEvent { ref event, .. } = event {
-            match event {
-                WindowEvent::CloseRequested => {
-                    elwt.exit();
-                }
-
-                WindowEvent::Resized(_) => {
-                    renderer.update_surface().unwrap();
-                }
-
-                WindowEvent::RedrawRequested => {
-                    time += 1;
+        tile((-2, 3), straight(Right)),
+        tile((-1, 3), straight(Right)),
+        tile((0,  3), straight(Right)),
+        tile((1,  3), straight(Right)),
+        tile((2,  3), straight(Right)),
+        tile((3,  3), straight(Right)),
+        tile((4,  3), straight(Right)),
+        tile((5,  3), straight(Right)),
+        tile((8,  3), straight(Right)),
+        tile((9,  3), straight(Right)),
+        tile((10, 3), straight(Right)),
+        tile((11, 3), straight(Right)),
+        tile((12, 3), straight(Right)),
+        tile((13, 3), straight(Right)),
+        tile((14, 3), straight(Right)),
+    ]);
 
-                    for idx in 0..vehicles.len() {
-                        let (left, right)    = vehicles.split_at_mut(idx);
-                        let (vehicle, right) = right.split_at_mut(1);
+    let markers = HashMap::from([(Point2::new(6, 1), Marker::Stoplight)]);
 
-                        let vehicle = &mut vehicle[0];
-                        let vehicles =
-                            left
-                                .iter()
-                                .chain(right.as_ref())
-                                .collect::<Vec<_>>();
+    let mut options = Options::parse(std::env::args().skip(1))?;
 
-                        vehicle.update(&vehicles, &tiles, &stop_signs, &stoplights).unwrap();
-                    }
+    let (grid, editor, demand) =
+        match &options.import {
+            Some(source) => {
+                let (grid, demand) = import::load(source, &options)?;
+                let editor         = import::editor(&grid);
 
-                    for stop_sign in &mut stop_signs {
-                        stop_sign.update(&vehicles);
-                    }
-
-                    for stoplight in &mut stoplights {
-                        stoplight.update(time);
-                    }
+                options.map = import::path(source);
 
-                    vehicles.retain(|vehicle| {
-                        match vehicle.dir.out_dir() {
-                            Cardinal::Up    => vehicle.tile_pos.y > -3,
-                            Cardinal::Down  => vehicle.tile_pos.y < 7,
-                            Cardinal::Left  => vehicle.tile_pos.x > -2,
-                            Cardinal::Right => vehicle.tile_pos.x < 14,
-                        }
-                    });
+                (grid, editor, demand)
+            }
 
-                    if time % 60 == 0 {
-                        let chosen =
-                            *possible.choose(&mut rand::thread_rng()).unwrap();
+            None => {
+                let editor =
+                    if options.map.exists() {
+                        Editor::load(&options.map)?
+                    } else {
+                        Editor::from_tiles(&tiles, &markers)
+                    };
 
-                        if !vehicles.iter().any(|vehicle| vehicle.tile_pos == chosen.tile_pos) {
-                            vehicles.push(chosen);
-                        }
-                    }
+                (editor.grid()?, editor, Vec::new())
+            }
+        };
 
-                    renderer.update(&vehicles, &tiles, &stoplights).unwrap();
+    let seed = options.seed.unwrap_or_else(rand::random);
 
-                    renderer.update(
-                        &vehicles,
-                        &tiles,
-                        &stop_signs,
-                        &stoplights,
-                    ).unwrap();
-                }
+    let mut simulation = Simulation::new(grid, seed, demand);
 
-                _ => {}
-            }
-        }
-    });
+    if let Some(path) = &options.fcd {
+        simulation.record(path)?;
+    }
 
-    Ok(())
+    match options.frontend {
+        FrontendKind::Window   => window(simulation, editor, options),
+        FrontendKind::Raster   => offscreen(simulation, &editor, &options),
+        FrontendKind::Terminal => terminal(simulation),
+    }
 }
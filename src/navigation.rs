@@ -0,0 +1,143 @@
+use renderer::camera::Camera;
+
+use nalgebra::{Point2, Vector2};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, NamedKey};
+
+pub const ZOOM_STEP:       f32 = 1.1;
+pub const PIXELS_PER_LINE: f32 = 40.0;
+pub const CLICK_SLOP:      f32 = 4.0;
+
+pub enum Command {
+    Fit,
+    FollowNext,
+    ToggleDebug,
+    ToggleHud,
+    Pause,
+    Step,
+    Faster,
+    Slower,
+    Restart,
+    Select(Point2<f32>),
+    AdvancePhase,
+    HoldPhase,
+    CycleControl,
+}
+
+pub struct Navigation {
+    cursor:   Point2<f32>,
+    dragging: bool,
+    pressed:  Option<Point2<f32>>,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Navigation {
+    pub fn new() -> Self {
+        Self {
+            cursor:   Point2::origin(),
+            dragging: false,
+            pressed:  None,
+        }
+    }
+
+    pub fn handle(&mut self, event: &WindowEvent, camera: &mut Camera, editing: bool) -> Option<Command> {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = Point2::new(position.x as f32, position.y as f32);
+
+                if self.dragging {
+                    camera.pan(cursor - self.cursor);
+                }
+
+                self.cursor = cursor;
+            }
+
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pans =
+                    match button {
+                        MouseButton::Middle => true,
+                        MouseButton::Left   => !editing,
+                        _                   => false,
+                    };
+
+                if pans {
+                    self.dragging = *state == ElementState::Pressed;
+                }
+
+                if *button == MouseButton::Left && !editing {
+                    match state {
+                        ElementState::Pressed => self.pressed = Some(self.cursor),
+
+                        ElementState::Released => {
+                            let clicked =
+                                self.pressed
+                                    .take()
+                                    .is_some_and(|pressed| (self.cursor - pressed).norm() <= CLICK_SLOP);
+
+                            if clicked {
+                                return Some(Command::Select(self.cursor));
+                            }
+                        }
+                    }
+                }
+            }
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines =
+                    match delta {
+                        MouseScrollDelta::LineDelta(_, y)   => *y,
+                        MouseScrollDelta::PixelDelta(delta) => delta.y as f32 / PIXELS_PER_LINE,
+                    };
+
+                camera.zoom_at(self.cursor, ZOOM_STEP.powf(lines));
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                match &event.logical_key {
+                    Key::Named(NamedKey::Escape) => {
+                        camera.follow = None;
+                    }
+
+                    Key::Named(NamedKey::ArrowLeft)  => camera.pan(Vector2::new( PIXELS_PER_LINE, 0.0)),
+                    Key::Named(NamedKey::ArrowRight) => camera.pan(Vector2::new(-PIXELS_PER_LINE, 0.0)),
+                    Key::Named(NamedKey::ArrowUp)    => camera.pan(Vector2::new(0.0,  PIXELS_PER_LINE)),
+                    Key::Named(NamedKey::ArrowDown)  => camera.pan(Vector2::new(0.0, -PIXELS_PER_LINE)),
+
+                    key if !editing => return command(key),
+
+                    _ => {}
+                }
+            }
+
+            _ => {}
+        }
+
+        None
+    }
+}
+
+fn command(key: &Key) -> Option<Command> {
+    match key {
+        Key::Character(ch) if ch.eq_ignore_ascii_case("f") => Some(Command::Fit),
+        Key::Character(ch) if ch.eq_ignore_ascii_case("v") => Some(Command::FollowNext),
+        Key::Character(ch) if ch.eq_ignore_ascii_case("b") => Some(Command::ToggleDebug),
+        Key::Character(ch) if ch.eq_ignore_ascii_case("h") => Some(Command::ToggleHud),
+        Key::Character(ch) if ch.eq_ignore_ascii_case("n") => Some(Command::Restart),
+        Key::Character(ch) if ch.eq_ignore_ascii_case("l") => Some(Command::HoldPhase),
+        Key::Character(ch) if ch.eq_ignore_ascii_case("t") => Some(Command::CycleControl),
+
+        Key::Character(ch) if ch == "." => Some(Command::Step),
+        Key::Character(ch) if ch == "+" || ch == "=" => Some(Command::Faster),
+        Key::Character(ch) if ch == "-" => Some(Command::Slower),
+
+        Key::Named(NamedKey::Space) => Some(Command::Pause),
+        Key::Named(NamedKey::Enter) => Some(Command::AdvancePhase),
+
+        _ => None,
+    }
+}
@@ -0,0 +1,221 @@
+use routing::ascii_map::STOPLIGHT_FREQ;
+use routing::grid::{ControlPolicy, Streets};
+
+use nalgebra::Point2;
+use anyhow::{bail, Context, Result};
+
+use std::path::PathBuf;
+
+pub const MAP_FILE:     &str = "map.txt";
+pub const FRAMES:       u32  = 600;
+pub const FRAME_WIDTH:  u32  = 1280;
+pub const FRAME_HEIGHT: u32  = 800;
+pub const FPS:          u32  = 30;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FrontendKind {
+    Window,
+    Raster,
+    Terminal,
+}
+
+impl FrontendKind {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "window" | "gl"       => Ok(Self::Window),
+            "raster" | "headless" => Ok(Self::Raster),
+            "terminal" | "tui"    => Ok(Self::Terminal),
+
+            _ => bail!("unknown frontend {name:?}, expected window, raster or terminal"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Import {
+    Generate(usize, usize),
+    Osm(PathBuf),
+    Sumo(PathBuf),
+    Tiled(PathBuf),
+}
+
+pub fn policy(name: &str) -> Result<ControlPolicy> {
+    match name {
+        "none" | "uncontrolled" => Ok(ControlPolicy::Uncontrolled),
+        "stop-signs"            => Ok(ControlPolicy::StopSigns),
+        "stoplights"            => Ok(ControlPolicy::Stoplights(STOPLIGHT_FREQ)),
+        "checkerboard"          => Ok(ControlPolicy::Checkerboard(STOPLIGHT_FREQ)),
+
+        _ => bail!("unknown policy {name:?}, expected none, stop-signs, stoplights or checkerboard"),
+    }
+}
+
+pub struct Options {
+    pub map:      PathBuf,
+    pub import:   Option<Import>,
+    pub streets:  Streets,
+    pub policy:   Option<ControlPolicy>,
+    pub demand:   Option<PathBuf>,
+    pub fcd:      Option<PathBuf>,
+    pub frontend: FrontendKind,
+    pub headless: Option<PathBuf>,
+    pub export:   Option<PathBuf>,
+    pub svg:      Option<PathBuf>,
+    pub ffmpeg:   Option<PathBuf>,
+    pub frames:   u32,
+    pub every:    u32,
+    pub fps:      u32,
+    pub width:    u32,
+    pub height:   u32,
+    pub region:   Option<(Point2<i32>, Point2<i32>)>,
+    pub overlay:  bool,
+    pub speed:    bool,
+    pub debug:    bool,
+    pub seed:     Option<u64>,
+    pub assets:   Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self {
+            map:      PathBuf::from(MAP_FILE),
+            import:   None,
+            streets:  Streets::TwoWay,
+            policy:   None,
+            demand:   None,
+            fcd:      None,
+            frontend: FrontendKind::Window,
+            headless: None,
+            export:   None,
+            svg:      None,
+            ffmpeg:   None,
+            frames:   FRAMES,
+            every:    1,
+            fps:      FPS,
+            width:    FRAME_WIDTH,
+            height:   FRAME_HEIGHT,
+            region:   None,
+            overlay:  false,
+            speed:    false,
+            debug:    false,
+            seed:     None,
+            assets:   None,
+        }
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut options  = Self::new();
+        let mut args     = args.into_iter();
+        let mut frontend = None;
+        let mut imports  = Vec::new();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("missing value for {arg}"));
+
+            match arg.as_str() {
+                "--frontend" => frontend         = Some(FrontendKind::parse(&value()?)?),
+                "--headless" => options.headless = Some(PathBuf::from(value()?)),
+                "--export"   => options.export   = Some(PathBuf::from(value()?)),
+                "--svg"      => options.svg      = Some(PathBuf::from(value()?)),
+                "--ffmpeg"   => options.ffmpeg   = Some(PathBuf::from(value()?)),
+                "--assets"   => options.assets   = Some(PathBuf::from(value()?)),
+                "--frames"   => options.frames   = value()?.parse().context("invalid --frames")?,
+                "--every"    => options.every    = value()?.parse().context("invalid --every")?,
+                "--fps"      => options.fps      = value()?.parse().context("invalid --fps")?,
+                "--seed"     => options.seed     = Some(value()?.parse().context("invalid --seed")?),
+                "--overlay"  => options.overlay  = true,
+                "--speed"    => options.speed    = true,
+                "--debug"    => options.debug    = true,
+                "--one-way"  => options.streets  = Streets::OneWay,
+                "--policy"   => options.policy   = Some(policy(&value()?)?),
+                "--osm"      => imports.push(Import::Osm(PathBuf::from(value()?))),
+                "--sumo"     => imports.push(Import::Sumo(PathBuf::from(value()?))),
+                "--tiled"    => imports.push(Import::Tiled(PathBuf::from(value()?))),
+                "--demand"   => options.demand   = Some(PathBuf::from(value()?)),
+                "--fcd"      => options.fcd      = Some(PathBuf::from(value()?)),
+
+                "--generate" => {
+                    let size = value()?;
+
+                    let (cols, rows) = size.split_once('x').context("--generate expects COLSxROWS")?;
+
+                    imports.push(Import::Generate(
+                        cols.parse().context("invalid --generate columns")?,
+                        rows.parse().context("invalid --generate rows")?,
+                    ));
+                }
+
+                "--size" => {
+                    let size = value()?;
+
+                    let (width, height) = size.split_once('x').context("--size expects WIDTHxHEIGHT")?;
+
+                    options.width  = width.parse().context("invalid --size width")?;
+                    options.height = height.parse().context("invalid --size height")?;
+                }
+
+                "--region" => {
+                    let region = value()?;
+
+                    let corners =
+                        region
+                            .split(',')
+                            .map(|part| part.trim().parse::<i32>())
+                            .collect::<Result<Vec<_>, _>>()
+                            .ok()
+                            .filter(|corners| corners.len() == 4)
+                            .context("--region expects X0,Y0,X1,Y1 in tiles")?;
+
+                    let min = Point2::new(corners[0].min(corners[2]), corners[1].min(corners[3]));
+                    let max = Point2::new(corners[0].max(corners[2]), corners[1].max(corners[3]));
+
+                    options.region = Some((min, max));
+                }
+
+                flag if flag.starts_with("--") => bail!("unknown option {flag}"),
+
+                _ => options.map = PathBuf::from(&arg),
+            }
+        }
+
+        if imports.len() > 1 {
+            bail!("only one map source can be given");
+        }
+
+        options.import = imports.pop();
+
+        let generated = matches!(options.import, Some(Import::Generate(..)));
+
+        if !generated && (options.streets == Streets::OneWay || options.policy.is_some()) {
+            bail!("--one-way and --policy need --generate");
+        }
+
+        if options.demand.is_some() && !matches!(options.import, Some(Import::Sumo(_))) {
+            bail!("--demand needs --sumo");
+        }
+
+        let offscreen = options.headless.is_some() || options.export.is_some() || options.svg.is_some();
+
+        options.frontend =
+            match frontend {
+                Some(frontend)    => frontend,
+                None if offscreen => FrontendKind::Raster,
+                None              => FrontendKind::Window,
+            };
+
+        match options.frontend {
+            FrontendKind::Raster if !offscreen => bail!("the raster frontend needs --headless, --export or --svg"),
+            FrontendKind::Raster               => {}
+            _ if offscreen                     => bail!("--headless, --export and --svg need the raster frontend"),
+            _                                  => {}
+        }
+
+        Ok(options)
+    }
+}
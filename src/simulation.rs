@@ -0,0 +1,220 @@
+use routing::direction::TileDirection;
+use routing::grid::Grid;
+use routing::random;
+use routing::sumo::{Demand, FcdWriter};
+use routing::vehicle::Vehicle;
+
+use renderer::frontend::World;
+use renderer::scene::Overlay;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use nalgebra::Point2;
+use anyhow::{Context, Result};
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::mem;
+use std::path::Path;
+
+pub const SPAWN_INTERVAL:   u32   = 60;
+pub const INITIAL_VEHICLES:  usize = 4;
+pub const THROUGHPUT_WINDOW: u32   = 60 * 60;
+
+pub struct Simulation {
+    pub grid:     Grid,
+    pub vehicles: Vec<Vehicle>,
+    pub time:     u32,
+    pub seed:     u64,
+    pub exited:   u32,
+    pub demand:   Vec<Demand>,
+    pub fcd:      Option<FcdWriter<BufWriter<File>>>,
+
+    exits:   VecDeque<u32>,
+    elapsed: u32,
+}
+
+impl Simulation {
+    pub fn new(grid: Grid, seed: u64, demand: Vec<Demand>) -> Self {
+        random::seed(seed);
+
+        let initial = if demand.is_empty() { INITIAL_VEHICLES } else { 0 };
+
+        let vehicles =
+            random::with(|rng| {
+                grid.entries
+                    .choose_multiple(rng, initial)
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .into_iter()
+            .map(|pos| spawn(pos, &grid))
+            .collect();
+
+        let mut simulation = Self {
+            grid,
+            vehicles,
+            time:    0,
+            seed,
+            exited:  0,
+            demand,
+            fcd:     None,
+            exits:   VecDeque::new(),
+            elapsed: 0,
+        };
+
+        simulation.release();
+        simulation
+    }
+
+    pub fn record<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let file = File::create(path).context("failed to create FCD file")?;
+
+        self.fcd = Some(FcdWriter::new(BufWriter::new(file))?);
+
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(fcd) = self.fcd.take() {
+            fcd.finish()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn reset(&mut self, grid: Grid) {
+        self.elapsed += self.time;
+
+        self.grid   = grid;
+        self.time   = 0;
+        self.exited = 0;
+        self.vehicles.clear();
+        self.exits.clear();
+    }
+
+    pub fn restart(&mut self, grid: Grid, seed: u64) {
+        let demand = mem::take(&mut self.demand);
+        let fcd     = self.fcd.take();
+        let elapsed = self.elapsed + self.time;
+
+        *self        = Self::new(grid, seed, demand);
+        self.fcd     = fcd;
+        self.elapsed = elapsed;
+    }
+
+    pub fn average_speed(&self) -> f32 {
+        if self.vehicles.is_empty() {
+            return 0.0;
+        }
+
+        self.vehicles.iter().map(Vehicle::speed).sum::<f32>() / self.vehicles.len() as f32
+    }
+
+    pub fn throughput(&self) -> f32 {
+        let window = self.time.clamp(1, THROUGHPUT_WINDOW);
+
+        self.exits.len() as f32 * (60.0 * 60.0) / window as f32
+    }
+
+    pub fn world<'a>(&'a self, overlay: Option<&'a Overlay<'a>>) -> World<'a> {
+        World {
+            time:     self.time,
+            vehicles: &self.vehicles,
+            tiles:    &self.grid.tiles,
+            controls: &self.grid.controls,
+            overlay,
+            debug:    false,
+        }
+    }
+
+    pub fn step(&mut self) -> Result<()> {
+        self.time += 1;
+
+        for idx in 0..self.vehicles.len() {
+            let (left, right)    = self.vehicles.split_at_mut(idx);
+            let (vehicle, right) = right.split_at_mut(1);
+
+            let vehicle = &mut vehicle[0];
+            let vehicles =
+                left
+                    .iter()
+                    .chain(right.as_ref())
+                    .collect::<Vec<_>>();
+
+            vehicle.update(&vehicles, &self.grid)?;
+        }
+
+        for control in &mut self.grid.controls {
+            control.update(self.time, &self.vehicles);
+        }
+
+        let before = self.vehicles.len();
+
+        self.vehicles.retain(|vehicle| !self.grid.exited(vehicle));
+
+        for _ in self.vehicles.len()..before {
+            self.exits.push_back(self.time);
+            self.exited += 1;
+        }
+
+        while self.exits.front().is_some_and(|&time| time + THROUGHPUT_WINDOW <= self.time) {
+            self.exits.pop_front();
+        }
+
+        if !self.demand.is_empty() {
+            self.release();
+        } else if self.time % SPAWN_INTERVAL == 0 {
+            if let Some(&pos) = random::with(|rng| self.grid.entries.choose(rng)) {
+                self.insert(pos);
+            }
+        }
+
+        if let Some(fcd) = &mut self.fcd {
+            fcd.timestep(self.elapsed + self.time, &self.vehicles)?;
+        }
+
+        Ok(())
+    }
+
+    fn release(&mut self) {
+        let due =
+            self.demand
+                .iter()
+                .filter(|demand| demand.due(self.time))
+                .map(|demand| demand.entry)
+                .collect::<Vec<_>>();
+
+        for pos in due {
+            self.insert(pos);
+        }
+    }
+
+    fn insert(&mut self, pos: Point2<f32>) {
+        let on_road =
+            self.grid.tiles
+                .at_pos(&pos)
+                .is_some_and(|tile| matches!(tile.dir, TileDirection::Constant(_)));
+
+        if !on_road {
+            return;
+        }
+
+        let chosen = spawn(pos, &self.grid);
+
+        if !self.vehicles.iter().any(|vehicle| vehicle.tile_pos == chosen.tile_pos) {
+            self.vehicles.push(chosen);
+        }
+    }
+}
+
+fn spawn(pos: Point2<f32>, grid: &Grid) -> Vehicle {
+    Vehicle::new(
+        pos,
+        8.0,
+        random::with(|rng| rng.gen_range(2.0..10.0)),
+        grid,
+    )
+}